@@ -1,30 +1,135 @@
+mod images;
+mod transcode;
+
+use std::collections::HashMap;
 use std::path::PathBuf;
-use axum::{extract::{Path, State}, Json};
+use axum::{extract::{Path, Query, State}, Json};
 use std::sync::Arc;
-use axum::http::{header, StatusCode};
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::Response;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tokio::fs::File;
 use tokio_util::io::ReaderStream;
-use entities::{album, artist, track};
+use entities::{album, artist, genre, lyrics, playlist, scan_issue, scan_job, track, track_artist};
+use entities::sea_orm_active_enums::{ArtistRole, GenreKind, IssueReason, LyricsSource, ScanState};
 use service::artist::ArtistService;
 use service::album::AlbumService;
-use service::track::TrackService;
-use scanner::Scanner;
+use service::genre::GenreService;
+use service::track::{TrackFilter, TrackService, LOSSLESS_CODECS};
+use service::track_artist::TrackArtistService;
+use service::lyrics::LyricsService;
+use service::path::stored_path;
+use service::playlist::PlaylistService;
+use service::scan_job::{ScanIssueCreate, ScanJobService};
+use scanner::{parse_lrc, plain_lyrics, read_embedded_picture, LyricsLine, ScanError, Scanner};
+use crate::images::ArtworkQuery;
+use crate::transcode::{Encoding, Segment, StreamQuery, TranscodeFormat};
+
+pub use crate::images::ImageCache;
 
 #[derive(Clone)]
 pub struct AppState {
     pub artist_service: Arc<ArtistService>,
     pub album_service: Arc<AlbumService>,
     pub track_service: Arc<TrackService>,
-    pub scanner: Arc<Scanner>
+    pub track_artist_service: Arc<TrackArtistService>,
+    pub genre_service: Arc<GenreService>,
+    pub lyrics_service: Arc<LyricsService>,
+    pub playlist_service: Arc<PlaylistService>,
+    pub scan_job_service: Arc<ScanJobService>,
+    pub scanner: Arc<Scanner>,
+    pub image_cache: Arc<ImageCache>,
+    /// ffmpeg binary used for transcoding
+    pub ffmpeg_path: String
 }
 #[derive(Serialize)]
 pub struct TrackDTO {
     id: i32,
     title: String,
-    duration: i32,
+    /// Duration in milliseconds, null when it could not be read
+    duration: Option<i64>,
     track_number: i32,
+    disc_number: i32,
+    disc_total: Option<i32>,
+    codec: Option<String>,
+    container: Option<String>,
+    lossless: Option<bool>,
+    /// Audio bitrate in kbps
+    bitrate: Option<i32>,
+    /// Sample rate in Hz
+    sample_rate: Option<i32>,
+    bit_depth: Option<i32>,
+    channels: Option<i32>,
+    /// File size in bytes
+    file_size: Option<i64>,
+    /// ReplayGain track gain in dB
+    track_gain: Option<f64>,
+    track_peak: Option<f64>,
+    /// Preferred copy of the same song when this track is a lesser copy
+    duplicate_of: Option<i32>,
+    artists: Vec<TrackArtistDTO>,
+}
+
+/// Filters for listing tracks, for example `?lossless=false` for every lossy track
+#[derive(Deserialize)]
+pub struct TrackQuery {
+    codec: Option<String>,
+    container: Option<String>,
+    lossless: Option<bool>,
+    min_sample_rate: Option<i32>,
+    min_bit_depth: Option<i32>,
+    hide_duplicates: Option<bool>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl From<TrackQuery> for TrackFilter {
+    fn from(query: TrackQuery) -> Self {
+        TrackFilter {
+            codec: query.codec,
+            container: query.container,
+            lossless: query.lossless,
+            min_sample_rate: query.min_sample_rate,
+            min_bit_depth: query.min_bit_depth,
+            hide_duplicates: query.hide_duplicates,
+            limit: query.limit,
+            offset: query.offset,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TrackArtistDTO {
+    id: i32,
+    name: String,
+    role: String,
+}
+
+impl From<(track_artist::Model, artist::Model)> for TrackArtistDTO {
+    fn from((credit, artist): (track_artist::Model, artist::Model)) -> Self {
+        TrackArtistDTO {
+            id: artist.id,
+            name: artist.name,
+            role: role_name(&credit.role).to_string()
+        }
+    }
+}
+
+fn role_name(role: &ArtistRole) -> &'static str {
+    match role {
+        ArtistRole::Primary => "primary",
+        ArtistRole::Featured => "featured",
+        ArtistRole::Remixer => "remixer",
+        ArtistRole::Composer => "composer",
+    }
+}
+
+/// A track on another artist's album the artist is credited on
+#[derive(Serialize)]
+pub struct AppearanceDTO {
+    album_id: i32,
+    role: String,
+    track: TrackDTO,
 }
 
 impl From<track::Model> for TrackDTO {
@@ -33,7 +138,96 @@ impl From<track::Model> for TrackDTO {
             id: track.id,
             title: track.title,
             duration: track.duration,
-            track_number: track.track_number
+            track_number: track.track_number,
+            disc_number: track.disc_number,
+            disc_total: track.disc_total,
+            lossless: track.codec.as_deref().map(|codec| LOSSLESS_CODECS.contains(&codec)),
+            codec: track.codec,
+            container: track.container,
+            bitrate: track.bitrate,
+            sample_rate: track.sample_rate,
+            bit_depth: track.bit_depth,
+            channels: track.channels,
+            file_size: track.file_size,
+            track_gain: track.track_gain,
+            track_peak: track.track_peak,
+            duplicate_of: track.duplicate_of,
+            artists: vec![]
+        }
+    }
+}
+
+/// A song found several times in the library, the preferred copy is the best quality one
+#[derive(Serialize)]
+pub struct DuplicateGroupDTO {
+    preferred: TrackDTO,
+    copies: Vec<TrackDTO>,
+}
+
+#[derive(Serialize)]
+pub struct PlaylistDTO {
+    id: i32,
+    name: String,
+    /// Playlist file the playlist is imported from, None for playlists created by users
+    path: Option<String>,
+    track_count: usize,
+    /// Entries of the playlist file that did not match a track
+    unresolved_entries: Vec<String>,
+}
+
+impl From<playlist::Model> for PlaylistDTO {
+    fn from(playlist: playlist::Model) -> Self {
+        PlaylistDTO {
+            id: playlist.id,
+            name: playlist.name,
+            path: playlist.path,
+            track_count: playlist.tracks.len(),
+            unresolved_entries: playlist.unresolved_entries
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct LyricsDTO {
+    synced: bool,
+    /// Where the lyrics were found, `sidecar` for `.lrc` files or `embedded` for tags
+    source: String,
+    /// The lyrics without timestamps
+    text: String,
+    /// Time-synced lines, empty for unsynchronized lyrics
+    lines: Vec<LyricsLineDTO>,
+}
+
+#[derive(Serialize)]
+pub struct LyricsLineDTO {
+    /// Time the line starts at in milliseconds
+    time: i64,
+    text: String,
+}
+
+impl From<LyricsLine> for LyricsLineDTO {
+    fn from(line: LyricsLine) -> Self {
+        LyricsLineDTO {
+            time: line.time,
+            text: line.text
+        }
+    }
+}
+
+impl From<lyrics::Model> for LyricsDTO {
+    fn from(lyrics: lyrics::Model) -> Self {
+        let lines = match lyrics.synced {
+            true => parse_lrc(&lyrics.content).into_iter().map(LyricsLineDTO::from).collect(),
+            false => vec![],
+        };
+        LyricsDTO {
+            synced: lyrics.synced,
+            source: match lyrics.source {
+                LyricsSource::Sidecar => "sidecar",
+                LyricsSource::Embedded => "embedded",
+            }.to_string(),
+            text: plain_lyrics(&lyrics.content),
+            lines
         }
     }
 }
@@ -43,6 +237,12 @@ pub struct AlbumDTO {
     id: i32,
     title: String,
     year: i32,
+    artist_id: i32,
+    compilation: bool,
+    has_cover: bool,
+    /// ReplayGain album gain in dB
+    album_gain: Option<f64>,
+    album_peak: Option<f64>,
 }
 
 impl From<album::Model> for AlbumDTO {
@@ -50,26 +250,160 @@ impl From<album::Model> for AlbumDTO {
         AlbumDTO {
             id: album.id,
             title: album.title,
-            year: album.release_year
+            year: album.release_year,
+            artist_id: album.artist_id,
+            compilation: album.compilation,
+            has_cover: album.cover_path.is_some(),
+            album_gain: album.album_gain,
+            album_peak: album.album_peak
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct GenreDTO {
+    id: i32,
+    name: String,
+    /// `genre`, `mood` or `style`
+    kind: String,
+    album_count: i64,
+}
+
+impl From<genre::Model> for GenreDTO {
+    fn from(genre: genre::Model) -> Self {
+        GenreDTO {
+            id: genre.id,
+            name: genre.name,
+            kind: genre_kind_name(&genre.kind).to_string(),
+            album_count: 0
         }
     }
 }
 
+fn genre_kind_name(kind: &GenreKind) -> &'static str {
+    match kind {
+        GenreKind::Genre => "genre",
+        GenreKind::Mood => "mood",
+        GenreKind::Style => "style",
+    }
+}
+
+/// Filters genres by kind, for example `?kind=mood`
+#[derive(Deserialize)]
+pub struct GenreQuery {
+    kind: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct ArtistDTO {
     id: i32,
-    name: String
+    name: String,
+    has_image: bool
 }
 
 impl From<artist::Model> for ArtistDTO {
     fn from(artist: artist::Model) -> Self {
         ArtistDTO {
             id: artist.id,
-            name: artist.name
+            name: artist.name,
+            has_image: artist.image_path.is_some()
         }
     }
 }
 
+#[derive(Serialize)]
+pub struct ScanJobDTO {
+    id: i32,
+    state: String,
+    /// RFC 3339 timestamps
+    started_at: String,
+    finished_at: Option<String>,
+    artists_total: i32,
+    artists_scanned: i32,
+    artists_added: i32,
+    artists_updated: i32,
+    artists_removed: i32,
+    albums_added: i32,
+    albums_updated: i32,
+    albums_removed: i32,
+    tracks_added: i32,
+    tracks_updated: i32,
+    tracks_removed: i32,
+    errors: Vec<String>,
+}
+
+impl From<scan_job::Model> for ScanJobDTO {
+    fn from(scan_job: scan_job::Model) -> Self {
+        let state = match scan_job.state {
+            ScanState::Running => "running",
+            ScanState::Completed => "completed",
+            ScanState::Failed => "failed",
+            ScanState::Cancelled => "cancelled",
+        };
+        let errors = scan_job.errors.as_array()
+            .map(|errors| errors.iter().filter_map(|e| e.as_str()).map(|e| e.to_string()).collect())
+            .unwrap_or_default();
+        ScanJobDTO {
+            id: scan_job.id,
+            state: state.to_string(),
+            started_at: scan_job.started_at.to_rfc3339(),
+            finished_at: scan_job.finished_at.map(|finished_at| finished_at.to_rfc3339()),
+            artists_total: scan_job.artists_total,
+            artists_scanned: scan_job.artists_scanned,
+            artists_added: scan_job.artists_added,
+            artists_updated: scan_job.artists_updated,
+            artists_removed: scan_job.artists_removed,
+            albums_added: scan_job.albums_added,
+            albums_updated: scan_job.albums_updated,
+            albums_removed: scan_job.albums_removed,
+            tracks_added: scan_job.tracks_added,
+            tracks_updated: scan_job.tracks_updated,
+            tracks_removed: scan_job.tracks_removed,
+            errors,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ScanIssueDTO {
+    path: String,
+    reason: String,
+}
+
+impl From<scan_issue::Model> for ScanIssueDTO {
+    fn from(scan_issue: scan_issue::Model) -> Self {
+        ScanIssueDTO {
+            path: scan_issue.path,
+            reason: issue_reason_name(&scan_issue.reason).to_string(),
+        }
+    }
+}
+
+impl From<ScanIssueCreate> for ScanIssueDTO {
+    fn from(scan_issue: ScanIssueCreate) -> Self {
+        ScanIssueDTO {
+            path: scan_issue.path,
+            reason: issue_reason_name(&scan_issue.reason).to_string(),
+        }
+    }
+}
+
+fn issue_reason_name(reason: &IssueReason) -> &'static str {
+    match reason {
+        IssueReason::BadNaming => "bad_naming",
+        IssueReason::MissingReleaseYear => "missing_release_year",
+        IssueReason::UnexpectedLocation => "unexpected_location",
+        IssueReason::UnreadableTags => "unreadable_tags",
+        IssueReason::UnsupportedExtension => "unsupported_extension",
+        IssueReason::UnresolvedPlaylistEntry => "unresolved_playlist_entry",
+    }
+}
+
+#[derive(Serialize)]
+pub struct ScanStartedDTO {
+    id: i32
+}
+
 pub async fn get_all_artists(
     State(state): State<AppState>
 ) -> Json<Vec<ArtistDTO>> {
@@ -103,6 +437,37 @@ pub async fn get_albums_by_artist(
     Json(albums)
 }
 
+pub async fn get_all_genres(
+    Query(query): Query<GenreQuery>,
+    State(state): State<AppState>
+) -> Result<Json<Vec<GenreDTO>>, StatusCode> {
+    let kind = match query.kind.as_deref() {
+        None => None,
+        Some("genre") => Some(GenreKind::Genre),
+        Some("mood") => Some(GenreKind::Mood),
+        Some("style") => Some(GenreKind::Style),
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+    let genres = state.genre_service.get_all(kind).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let album_counts = state.genre_service.count_albums().await.unwrap_or_default();
+    let genres = genres.into_iter().map(|genre| GenreDTO {
+        album_count: album_counts.get(&genre.id).copied().unwrap_or(0),
+        ..GenreDTO::from(genre)
+    }).collect();
+    Ok(Json(genres))
+}
+
+pub async fn get_genre_albums(
+    Path(genre_id): Path<i32>,
+    State(state): State<AppState>
+) -> Result<Json<Vec<AlbumDTO>>, StatusCode> {
+    state.genre_service.get_by_id(genre_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let albums = state.genre_service.get_albums(genre_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(albums.into_iter().map(AlbumDTO::from).collect()))
+}
+
 pub async fn get_tracks_by_album(
     Path(album_id): Path<i32>,
     State(state): State<AppState>
@@ -112,24 +477,175 @@ pub async fn get_tracks_by_album(
         Some(tracks) => tracks,
         None => return Json(vec![]),
     };
-    let tracks: Vec<TrackDTO> = tracks.into_iter().map(TrackDTO::from).collect();
-    Json(tracks)
+    Json(tracks_with_artists(&state, tracks).await)
+}
+
+/// Lists the tracks matching the filters in the query
+pub async fn get_tracks(
+    Query(query): Query<TrackQuery>,
+    State(state): State<AppState>
+) -> Json<Vec<TrackDTO>> {
+    let tracks = state.track_service.get_filtered(TrackFilter::from(query)).await.unwrap_or_default();
+    Json(tracks_with_artists(&state, tracks).await)
+}
+
+/// Lists the songs found several times in the library, as detected by the last scan
+pub async fn get_duplicates(State(state): State<AppState>) -> Result<Json<Vec<DuplicateGroupDTO>>, StatusCode> {
+    let copies = state.track_service.get_duplicates().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let preferred_ids: Vec<i32> = copies.iter().filter_map(|track| track.duplicate_of).collect();
+    let preferred = state.track_service.get_by_ids(preferred_ids).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut copies_by_preferred: HashMap<i32, Vec<TrackDTO>> = HashMap::new();
+    for copy in tracks_with_artists(&state, copies).await {
+        if let Some(preferred_id) = copy.duplicate_of {
+            copies_by_preferred.entry(preferred_id).or_default().push(copy);
+        }
+    }
+    let mut groups: Vec<DuplicateGroupDTO> = tracks_with_artists(&state, preferred).await.into_iter()
+        .map(|preferred| DuplicateGroupDTO {
+            copies: copies_by_preferred.remove(&preferred.id).unwrap_or_default(),
+            preferred,
+        })
+        .collect();
+    groups.sort_by_key(|group| group.preferred.id);
+    Ok(Json(groups))
 }
 
-pub async fn rescan_library(State(state): State<AppState>) -> StatusCode {
+/// Returns the lyrics of the track, as plain text and as time-synced lines when available
+pub async fn get_track_lyrics(
+    Path(track_id): Path<i32>,
+    State(state): State<AppState>
+) -> Result<Json<LyricsDTO>, StatusCode> {
+    let lyrics = state.lyrics_service.get_by_track_id(track_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(LyricsDTO::from(lyrics)))
+}
+
+pub async fn get_all_playlists(State(state): State<AppState>) -> Json<Vec<PlaylistDTO>> {
+    let playlists = state.playlist_service.get_all().await.unwrap_or_default();
+    Json(playlists.into_iter().map(PlaylistDTO::from).collect())
+}
+
+pub async fn get_playlist_by_id(
+    Path(playlist_id): Path<i32>,
+    State(state): State<AppState>
+) -> Result<Json<PlaylistDTO>, StatusCode> {
+    let playlist = state.playlist_service.get_by_id(playlist_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(PlaylistDTO::from(playlist)))
+}
+
+/// Returns the tracks of the playlist in playlist order, a track listed twice is returned twice
+pub async fn get_playlist_tracks(
+    Path(playlist_id): Path<i32>,
+    State(state): State<AppState>
+) -> Result<Json<Vec<TrackDTO>>, StatusCode> {
+    let playlist = state.playlist_service.get_by_id(playlist_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let tracks: HashMap<i32, track::Model> = state.track_service.get_by_ids(playlist.tracks.clone()).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|track| (track.id, track))
+        .collect();
+    let tracks = playlist.tracks.iter().filter_map(|track_id| tracks.get(track_id).cloned()).collect();
+    Ok(Json(tracks_with_artists(&state, tracks).await))
+}
+
+/// Converts the tracks to DTOs including their credited artists
+async fn tracks_with_artists(state: &AppState, tracks: Vec<track::Model>) -> Vec<TrackDTO> {
+    let track_ids = tracks.iter().map(|track| track.id).collect();
+    let credits = state.track_artist_service.get_by_track_ids(track_ids).await.unwrap_or_default();
+    let mut artists: HashMap<i32, Vec<TrackArtistDTO>> = HashMap::new();
+    for credit in credits {
+        artists.entry(credit.0.track_id).or_default().push(TrackArtistDTO::from(credit));
+    }
+    tracks.into_iter().map(|track| {
+        let track_artists = artists.remove(&track.id).unwrap_or_default();
+        TrackDTO { artists: track_artists, ..TrackDTO::from(track) }
+    }).collect()
+}
+
+pub async fn get_artist_appearances(
+    Path(artist_id): Path<i32>,
+    State(state): State<AppState>
+) -> Json<Vec<AppearanceDTO>> {
+    let appearances = state.track_artist_service.get_appearances(artist_id).await.unwrap_or_default();
+    let appearances: Vec<AppearanceDTO> = appearances.into_iter().map(|(credit, track)| AppearanceDTO {
+        album_id: track.album_id,
+        role: role_name(&credit.role).to_string(),
+        track: TrackDTO::from(track),
+    }).collect();
+    Json(appearances)
+}
+
+/// Starts a scan job, responds with `409 Conflict` and the ID of the running job when a scan is already running
+pub async fn start_scan(State(state): State<AppState>) -> (StatusCode, Json<Option<ScanStartedDTO>>) {
+    match state.scanner.clone().start_scan().await {
+        Ok(id) => (StatusCode::OK, Json(Some(ScanStartedDTO { id }))),
+        Err(ScanError::AlreadyRunning(id)) => (StatusCode::CONFLICT, Json(Some(ScanStartedDTO { id }))),
+        Err(ScanError::Database(_)) => (StatusCode::INTERNAL_SERVER_ERROR, Json(None)),
+    }
+}
 
+/// Lists every file and directory a scan would skip, without writing to the database
+pub async fn dry_run_scan(State(state): State<AppState>) -> Result<Json<Vec<ScanIssueDTO>>, StatusCode> {
     let scanner = state.scanner.clone();
-    tokio::spawn(async move {
-        scanner.scan_library().await;
-    });
+    let stats = tokio::task::spawn_blocking(move || scanner.dry_run()).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let issues: Vec<ScanIssueDTO> = stats.take_issues().into_iter().map(ScanIssueDTO::from).collect();
+    Ok(Json(issues))
+}
+
+pub async fn get_scan_issues(
+    Path(scan_job_id): Path<i32>,
+    State(state): State<AppState>
+) -> Json<Vec<ScanIssueDTO>> {
+    let issues = state.scan_job_service.get_issues(scan_job_id).await.unwrap_or_default();
+    let issues: Vec<ScanIssueDTO> = issues.into_iter().map(ScanIssueDTO::from).collect();
+    Json(issues)
+}
 
-    StatusCode::OK
+pub async fn cancel_scan(
+    Path(scan_job_id): Path<i32>,
+    State(state): State<AppState>
+) -> StatusCode {
+    if state.scanner.cancel_scan(scan_job_id).await {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+pub async fn get_scan_jobs(
+    State(state): State<AppState>
+) -> Json<Vec<ScanJobDTO>> {
+    let scan_jobs = state.scan_job_service.get_all().await.unwrap_or_default();
+    let scan_jobs: Vec<ScanJobDTO> = scan_jobs.into_iter().map(ScanJobDTO::from).collect();
+    Json(scan_jobs)
+}
 
+pub async fn get_scan_job_by_id(
+    Path(scan_job_id): Path<i32>,
+    State(state): State<AppState>
+) -> Result<Json<ScanJobDTO>, StatusCode> {
+    let scan_job = state.scan_job_service.get_by_id(scan_job_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match scan_job {
+        Some(scan_job) => Ok(Json(ScanJobDTO::from(scan_job))),
+        None => Err(StatusCode::NOT_FOUND),
+    }
 }
 
 
+/// Streams the track file as is, or through ffmpeg when `?format=`, `?normalize=track|album` or `?offset=` is given.
+/// Tracks split from a CUE sheet are always cut from their file with ffmpeg.
 pub async fn stream_track(
     Path(track_id): Path<i32>,
+    Query(query): Query<StreamQuery>,
     State(state): State<AppState>,
 ) -> Result<Response, StatusCode> {
     let track = state.track_service.get_by_id(track_id).await
@@ -140,13 +656,133 @@ pub async fn stream_track(
         None => return Err(StatusCode::NOT_FOUND),
     };
 
-    let path: PathBuf = track.path.into();
+    let path = stored_path(&track.path, track.path_bytes.as_deref());
+    let segment = Segment::of(&track, query.offset);
+    if query.format.is_some() || query.normalize.is_some() || segment.is_some() {
+        let album = match query.normalize {
+            Some(_) => state.album_service.get_by_id(track.album_id).await.unwrap_or_default(),
+            None => None,
+        };
+        let gain = query.normalize.and_then(|mode| transcode::gain(mode, &track, album.as_ref()));
+        let encoding = match query.format {
+            Some(format) => Encoding::Transcode(format),
+            None if gain.is_none() => Encoding::original(track.codec.as_deref()),
+            None => Encoding::Transcode(TranscodeFormat::default()),
+        };
+        let body = transcode::transcode(&state.ffmpeg_path, &path, &encoding, segment, gain)?;
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, encoding.content_type())
+            .body(body)
+            .unwrap())
+    }
+
     let file = File::open(&path).await.map_err(|_| StatusCode::NOT_FOUND)?;
     let stream = ReaderStream::new(file);
 
     Ok(Response::builder()
         .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "audio/flac")
+        .header(header::CONTENT_TYPE, transcode::file_content_type(track.container.as_deref(), &path))
         .body(axum::body::Body::from_stream(stream))
         .unwrap())
 }
+
+/// How long clients may cache artwork before revalidating it
+const ARTWORK_MAX_AGE: u64 = 60 * 60 * 24 * 7;
+
+/// Serves the album cover, resized to fit `?size=` pixels when given
+pub async fn get_album_cover(
+    Path(album_id): Path<i32>,
+    Query(query): Query<ArtworkQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let album = state.album_service.get_by_id(album_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let cover_path = album.cover_path.ok_or(StatusCode::NOT_FOUND)?;
+    let cover_path = stored_path(&cover_path, album.cover_path_bytes.as_deref());
+    serve_artwork(cover_path, album.cover_embedded, query, &state.image_cache, &headers).await
+}
+
+/// Serves the artist image, resized to fit `?size=` pixels when given
+pub async fn get_artist_image(
+    Path(artist_id): Path<i32>,
+    Query(query): Query<ArtworkQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let artist = state.artist_service.get_by_id(artist_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let image_path = artist.image_path.ok_or(StatusCode::NOT_FOUND)?;
+    let image_path = stored_path(&image_path, artist.image_path_bytes.as_deref());
+    serve_artwork(image_path, false, query, &state.image_cache, &headers).await
+}
+
+/// Serves an image file, or the picture embedded in an audio file, optionally as a resized variant.
+/// The ETag is derived from the size and modification time of the file, so clients revalidate cheaply.
+async fn serve_artwork(path: PathBuf, embedded: bool, query: ArtworkQuery, image_cache: &ImageCache, headers: &HeaderMap) -> Result<Response, StatusCode> {
+    let metadata = tokio::fs::metadata(&path).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    let modified_at = metadata.modified().ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let version = format!("{:x}-{:x}", metadata.len(), modified_at);
+    let format = query.format.unwrap_or_default();
+    let etag = match query.size {
+        Some(size) => format!("\"{}-{}-{}\"", version, size, format.content_type()),
+        None => format!("\"{}\"", version),
+    };
+    let cache_control = format!("public, max-age={}", ARTWORK_MAX_AGE);
+
+    if headers.get(header::IF_NONE_MATCH).is_some_and(|value| value.as_bytes() == etag.as_bytes()) {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .body(axum::body::Body::empty())
+            .unwrap())
+    }
+
+    let (data, content_type) = if let Some(size) = query.size {
+        let source = path.to_string_lossy().to_string();
+        let data = image_cache.variant(source, version, size, format, move || {
+            if embedded {
+                read_embedded_picture(&path).map(|picture| picture.data)
+            } else {
+                std::fs::read(&path).ok()
+            }
+        }).await?;
+        (data, format.content_type().to_string())
+    } else if embedded {
+        let picture = tokio::task::spawn_blocking(move || read_embedded_picture(&path)).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        let content_type = picture.mime_type.unwrap_or_else(|| "image/jpeg".to_string());
+        (picture.data, content_type)
+    } else {
+        let data = tokio::fs::read(&path).await.map_err(|_| StatusCode::NOT_FOUND)?;
+        (data, image_content_type(&path).to_string())
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control)
+        .body(axum::body::Body::from(data))
+        .unwrap())
+}
+
+fn image_content_type(path: &std::path::Path) -> &'static str {
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use axum::http::StatusCode;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::Deserialize;
+
+/// Sizes variants are generated in, requested sizes are rounded up to the next one so the cache stays small
+const VARIANT_SIZES: [u32; 6] = [64, 128, 256, 512, 1024, 2048];
+const JPEG_QUALITY: u8 = 85;
+const WEBP_QUALITY: f32 = 80.0;
+
+#[derive(Deserialize)]
+pub struct ArtworkQuery {
+    /// Longest edge in pixels
+    pub size: Option<u32>,
+    pub format: Option<VariantFormat>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VariantFormat {
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+impl VariantFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            VariantFormat::Jpeg => "image/jpeg",
+            VariantFormat::Webp => "image/webp",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            VariantFormat::Jpeg => "jpg",
+            VariantFormat::Webp => "webp",
+        }
+    }
+}
+
+/// Resized artwork variants, cached on disk.
+/// When the cache grows past its limit, the least recently used variants are removed.
+pub struct ImageCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    variants: Mutex<Variants>,
+}
+
+/// Size and last use of every cached variant, read from the cache directory once on startup
+#[derive(Default)]
+struct Variants {
+    used: HashMap<PathBuf, (u64, SystemTime)>,
+    total_bytes: u64,
+}
+
+impl Variants {
+    fn insert(&mut self, path: PathBuf, size: u64, used_at: SystemTime) {
+        if let Some((previous_size, _)) = self.used.insert(path, (size, used_at)) {
+            self.total_bytes -= previous_size;
+        }
+        self.total_bytes += size;
+    }
+
+    fn touch(&mut self, path: &Path) {
+        if let Some((_, used_at)) = self.used.get_mut(path) {
+            *used_at = SystemTime::now();
+        }
+    }
+
+    /// Takes the least recently used variants out until the rest fits the limit, returns their paths
+    fn evict(&mut self, max_bytes: u64) -> Vec<PathBuf> {
+        if self.total_bytes <= max_bytes {
+            return vec![]
+        }
+        let mut variants: Vec<(PathBuf, u64, SystemTime)> = self.used.iter()
+            .map(|(path, (size, used_at))| (path.clone(), *size, *used_at))
+            .collect();
+        variants.sort_by_key(|(_, _, used_at)| *used_at);
+        let mut evicted = Vec::new();
+        for (path, size, _) in variants {
+            if self.total_bytes <= max_bytes {
+                break
+            }
+            self.used.remove(&path);
+            self.total_bytes -= size;
+            evicted.push(path);
+        }
+        evicted
+    }
+}
+
+impl ImageCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        let mut variants = Variants::default();
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if let Ok(modified) = metadata.modified() {
+                    variants.insert(entry.path(), metadata.len(), modified);
+                }
+            }
+        }
+        ImageCache { dir, max_bytes, variants: Mutex::new(variants) }
+    }
+
+    /// Returns the variant of the image, resizing and caching it on first request.
+    /// `version` identifies the source image, so variants of replaced images are not served.
+    pub async fn variant<F>(&self, source: String, version: String, size: u32, format: VariantFormat, load: F) -> Result<Vec<u8>, StatusCode>
+    where
+        F: FnOnce() -> Option<Vec<u8>> + Send + 'static,
+    {
+        let size = variant_size(size);
+        let key = blake3::hash(format!("{}\n{}\n{}", source, version, size).as_bytes()).to_hex();
+        let path = self.dir.join(format!("{}.{}", key, format.extension()));
+
+        if let Ok(data) = tokio::fs::read(&path).await {
+            // The modification time keeps the order of use across restarts
+            let _ = fs::File::options().write(true).open(&path).and_then(|file| file.set_modified(SystemTime::now()));
+            self.variants.lock().unwrap().touch(&path);
+            return Ok(data)
+        }
+
+        let dir = self.dir.clone();
+        let variant_path = path.clone();
+        let (data, stored) = tokio::task::spawn_blocking(move || -> Result<(Vec<u8>, bool), StatusCode> {
+            let source = load().ok_or(StatusCode::NOT_FOUND)?;
+            let data = resize(&source, size, format).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+            let stored = match store(&dir, &variant_path, &data) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("Unable to cache artwork variant {}: {}", variant_path.display(), e);
+                    false
+                }
+            };
+            Ok((data, stored))
+        }).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+        if stored {
+            self.evict(path, data.len() as u64).await;
+        }
+        Ok(data)
+    }
+
+    /// Records the stored variant, then removes the least recently used variants until the cache fits its limit
+    async fn evict(&self, path: PathBuf, size: u64) {
+        let evicted = {
+            let mut variants = self.variants.lock().unwrap();
+            variants.insert(path, size, SystemTime::now());
+            variants.evict(self.max_bytes)
+        };
+        for path in evicted {
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+    }
+}
+
+fn variant_size(size: u32) -> u32 {
+    VARIANT_SIZES.iter().copied()
+        .find(|variant| *variant >= size)
+        .unwrap_or(VARIANT_SIZES[VARIANT_SIZES.len() - 1])
+}
+
+/// Scales the image down to fit the size, images smaller than the size keep their dimensions
+fn resize(source: &[u8], size: u32, format: VariantFormat) -> Option<Vec<u8>> {
+    let image = image::load_from_memory(source).ok()?;
+    let image = if image.width() > size || image.height() > size {
+        image.resize(size, size, FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    let mut data = Cursor::new(Vec::new());
+    match format {
+        VariantFormat::Jpeg => {
+            let image = DynamicImage::ImageRgb8(image.to_rgb8());
+            image.write_with_encoder(JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY)).ok()?;
+        }
+        VariantFormat::Webp => {
+            let image = image.to_rgba8();
+            let encoded = webp::Encoder::from_rgba(&image, image.width(), image.height()).encode(WEBP_QUALITY);
+            data.get_mut().extend_from_slice(&encoded);
+        }
+    }
+    Some(data.into_inner())
+}
+
+/// Writes the variant to a temporary file first, so concurrent requests never read a partial variant
+fn store(dir: &Path, path: &Path, data: &[u8]) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, data)?;
+    fs::rename(&temporary, path)
+}
@@ -0,0 +1,195 @@
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll};
+use axum::body::Body;
+use axum::http::StatusCode;
+use serde::Deserialize;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::process::{Child, ChildStdout, Command};
+use tokio_util::io::ReaderStream;
+use entities::{album, track};
+
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    /// Applies the ReplayGain track or album gain, transcoding to FLAC unless another format is requested
+    pub normalize: Option<GainMode>,
+    pub format: Option<TranscodeFormat>,
+    /// Starts the stream this many milliseconds into the track
+    pub offset: Option<i64>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum GainMode {
+    Track,
+    /// Falls back to the track gain when the album has no gain
+    Album,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscodeFormat {
+    #[default]
+    Flac,
+    Mp3,
+    Opus,
+}
+
+impl TranscodeFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TranscodeFormat::Flac => "audio/flac",
+            TranscodeFormat::Mp3 => "audio/mpeg",
+            TranscodeFormat::Opus => "audio/ogg",
+        }
+    }
+
+    fn ffmpeg_args(&self) -> &'static [&'static str] {
+        match self {
+            TranscodeFormat::Flac => &["-c:a", "flac", "-f", "flac"],
+            TranscodeFormat::Mp3 => &["-c:a", "libmp3lame", "-q:a", "0", "-f", "mp3"],
+            TranscodeFormat::Opus => &["-c:a", "libopus", "-b:a", "160k", "-f", "ogg"],
+        }
+    }
+}
+
+/// Streams the track without decoding the audio, for lossy codecs whose frames can be cut as they are
+pub struct Remux {
+    muxer: &'static str,
+    content_type: &'static str,
+}
+
+/// How the audio is written to the stream
+pub enum Encoding {
+    Transcode(TranscodeFormat),
+    Remux(Remux),
+}
+
+impl Encoding {
+    /// Keeps the codec of lossy tracks by remuxing them, lossless tracks are encoded as FLAC
+    pub fn original(codec: Option<&str>) -> Self {
+        let remux = |muxer, content_type| Encoding::Remux(Remux { muxer, content_type });
+        match codec {
+            Some("mp3") => remux("mp3", "audio/mpeg"),
+            Some("aac") => remux("adts", "audio/aac"),
+            Some("vorbis" | "opus") => remux("ogg", "audio/ogg"),
+            _ => Encoding::Transcode(TranscodeFormat::Flac),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Encoding::Transcode(format) => format.content_type(),
+            Encoding::Remux(remux) => remux.content_type,
+        }
+    }
+
+    fn ffmpeg_args(&self) -> Vec<&'static str> {
+        match self {
+            Encoding::Transcode(format) => format.ffmpeg_args().to_vec(),
+            Encoding::Remux(remux) => vec!["-c:a", "copy", "-f", remux.muxer],
+        }
+    }
+}
+
+/// MIME type of the file as it is stored, from the container read by the scan, or the file extension for older tracks
+pub fn file_content_type(container: Option<&str>, path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    match container.unwrap_or(extension.as_str()) {
+        "flac" => "audio/flac",
+        "mpeg" | "mp3" => "audio/mpeg",
+        "mp4" | "m4a" | "alac" => "audio/mp4",
+        "aac" => "audio/aac",
+        "ogg" | "opus" => "audio/ogg",
+        "wav" => "audio/wav",
+        "aiff" => "audio/aiff",
+        "ape" => "audio/x-ape",
+        "wavpack" => "audio/x-wavpack",
+        "mpc" => "audio/x-musepack",
+        "asf" | "wma" => "audio/x-ms-wma",
+        "amr" => "audio/amr",
+        "dsf" | "dsd" => "audio/x-dsf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Part of the file to stream in milliseconds
+pub struct Segment {
+    start: i64,
+    end: Option<i64>,
+}
+
+impl Segment {
+    /// Returns the part of the file the track plays from the offset on, None when that is the whole file
+    pub fn of(track: &track::Model, offset: Option<i64>) -> Option<Self> {
+        let start = track.start_offset.unwrap_or(0) + offset.unwrap_or(0).max(0);
+        let end = track.end_offset;
+        if start == 0 && end.is_none() {
+            return None
+        }
+        Some(Segment { start, end })
+    }
+}
+
+/// Returns the gain in dB to apply for the mode, lowered where needed so the peak does not clip
+pub fn gain(mode: GainMode, track: &track::Model, album: Option<&album::Model>) -> Option<f64> {
+    let album_gain = album
+        .filter(|_| matches!(mode, GainMode::Album))
+        .and_then(|album| Some((album.album_gain?, album.album_peak)));
+    let (gain, peak) = album_gain.or_else(|| Some((track.track_gain?, track.track_peak)))?;
+    match peak.filter(|peak| *peak > 0.0) {
+        Some(peak) => Some(gain.min(-20.0 * peak.log10())),
+        None => Some(gain),
+    }
+}
+
+/// Streams the track through ffmpeg, cut to the segment and with the gain applied when given.
+/// ffmpeg is killed when the body is dropped, like when the client disconnects mid-stream.
+pub fn transcode(ffmpeg_path: &str, path: &Path, encoding: &Encoding, segment: Option<Segment>, gain: Option<f64>) -> Result<Body, StatusCode> {
+    let mut command = Command::new(ffmpeg_path);
+    command.args(["-hide_banner", "-v", "error"]);
+    if let Some(segment) = segment {
+        command.args(["-ss", &seconds(segment.start)]);
+        if let Some(end) = segment.end {
+            command.args(["-to", &seconds(end)]);
+        }
+    }
+    command.arg("-i").arg(path).arg("-vn");
+    if let (Some(gain), Encoding::Transcode(_)) = (gain, encoding) {
+        command.args(["-af", &format!("volume={:.2}dB", gain)]);
+    }
+    let mut child = command
+        .args(encoding.ffmpeg_args())
+        .arg("pipe:1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            println!("Unable to start ffmpeg for {}: {}", path.display(), e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let stdout = child.stdout.take().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Body::from_stream(ReaderStream::new(FfmpegOutput { stdout, _child: child })))
+}
+
+/// Output of a running ffmpeg process, which owns the process so it lives as long as the body.
+/// Dropping the output kills the process if it still runs, and the runtime reaps it in the background.
+struct FfmpegOutput {
+    stdout: ChildStdout,
+    _child: Child,
+}
+
+impl AsyncRead for FfmpegOutput {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+/// Formats milliseconds as the seconds ffmpeg expects for times
+fn seconds(milliseconds: i64) -> String {
+    format!("{}.{:03}", milliseconds / 1000, milliseconds % 1000)
+}
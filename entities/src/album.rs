@@ -2,7 +2,7 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "album")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -11,6 +11,17 @@ pub struct Model {
     pub path: String,
     pub release_year: i32,
     pub artist_id: i32,
+    pub compilation: bool,
+    pub cover_path: Option<String>,
+    pub cover_embedded: bool,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub album_gain: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub album_peak: Option<f64>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub path_bytes: Option<Vec<u8>>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub cover_path_bytes: Option<Vec<u8>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -23,6 +34,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Artist,
+    #[sea_orm(has_many = "super::album_genre::Entity")]
+    AlbumGenre,
     #[sea_orm(has_many = "super::track::Entity")]
     Track,
 }
@@ -33,6 +46,12 @@ impl Related<super::artist::Entity> for Entity {
     }
 }
 
+impl Related<super::album_genre::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AlbumGenre.def()
+    }
+}
+
 impl Related<super::track::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Track.def()
@@ -8,14 +8,21 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub name: String,
-    pub path: String,
+    pub path: Option<String>,
     pub checksum: Option<String>,
+    pub image_path: Option<String>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub path_bytes: Option<Vec<u8>>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub image_path_bytes: Option<Vec<u8>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::album::Entity")]
     Album,
+    #[sea_orm(has_many = "super::track_artist::Entity")]
+    TrackArtist,
 }
 
 impl Related<super::album::Entity> for Entity {
@@ -24,4 +31,19 @@ impl Related<super::album::Entity> for Entity {
     }
 }
 
+impl Related<super::track_artist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TrackArtist.def()
+    }
+}
+
+impl Related<super::track::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::track_artist::Relation::Track.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::track_artist::Relation::Artist.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
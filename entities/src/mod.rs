@@ -3,6 +3,15 @@
 pub mod prelude;
 
 pub mod album;
+pub mod album_genre;
 pub mod artist;
+pub mod genre;
+pub mod lyrics;
+pub mod playlist;
+pub mod scan_issue;
+pub mod scan_job;
+pub mod sea_orm_active_enums;
 pub mod track;
+pub mod track_artist;
+pub mod track_genre;
 pub mod user;
@@ -1,6 +1,14 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::album::Entity as Album;
+pub use super::album_genre::Entity as AlbumGenre;
 pub use super::artist::Entity as Artist;
+pub use super::genre::Entity as Genre;
+pub use super::lyrics::Entity as Lyrics;
+pub use super::playlist::Entity as Playlist;
+pub use super::scan_issue::Entity as ScanIssue;
+pub use super::scan_job::Entity as ScanJob;
 pub use super::track::Entity as Track;
+pub use super::track_artist::Entity as TrackArtist;
+pub use super::track_genre::Entity as TrackGenre;
 pub use super::user::Entity as User;
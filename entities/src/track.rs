@@ -2,7 +2,7 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "track")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -10,8 +10,32 @@ pub struct Model {
     pub title: String,
     pub path: String,
     pub track_number: i32,
-    pub duration: i32,
+    pub duration: Option<i64>,
     pub album_id: i32,
+    pub disc_number: i32,
+    pub disc_total: Option<i32>,
+    pub file_size: Option<i64>,
+    pub modified_at: Option<i64>,
+    pub content_hash: Option<String>,
+    pub codec: Option<String>,
+    pub container: Option<String>,
+    pub bitrate: Option<i32>,
+    pub sample_rate: Option<i32>,
+    pub bit_depth: Option<i32>,
+    pub channels: Option<i32>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub track_gain: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub track_peak: Option<f64>,
+    pub cue_track: i32,
+    pub start_offset: Option<i64>,
+    pub end_offset: Option<i64>,
+    pub duplicate_of: Option<i32>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub path_bytes: Option<Vec<u8>>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub acoustic_fingerprint: Option<Vec<u8>>,
+    pub acoustic_fingerprint_key: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -24,6 +48,20 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Album,
+    #[sea_orm(has_one = "super::lyrics::Entity")]
+    Lyrics,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::DuplicateOf",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::track_artist::Entity")]
+    TrackArtist,
+    #[sea_orm(has_many = "super::track_genre::Entity")]
+    TrackGenre,
 }
 
 impl Related<super::album::Entity> for Entity {
@@ -32,4 +70,31 @@ impl Related<super::album::Entity> for Entity {
     }
 }
 
+impl Related<super::lyrics::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Lyrics.def()
+    }
+}
+
+impl Related<super::track_artist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TrackArtist.def()
+    }
+}
+
+impl Related<super::track_genre::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TrackGenre.def()
+    }
+}
+
+impl Related<super::artist::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::track_artist::Relation::Artist.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::track_artist::Relation::Track.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -7,6 +7,29 @@ mod m20250319_153613_create_playlist_table;
 mod m20250320_162211_create_track_table;
 mod m20250510_091903_add_artist_checksum;
 mod m20250527_113751_alter_track_number;
+mod m20250606_101500_add_track_disc_number;
+mod m20250606_134200_alter_track_duration;
+mod m20250607_091200_add_track_fingerprint;
+mod m20250608_143000_add_unique_constraints;
+mod m20250610_183000_create_scan_job_table;
+mod m20250612_094500_create_scan_issue_table;
+mod m20250613_101000_add_track_disc_total;
+mod m20250614_120000_create_track_artist_table;
+mod m20250615_093000_add_artwork_paths;
+mod m20250616_104500_add_track_audio_properties;
+mod m20250617_091500_add_replay_gain;
+mod m20250618_140000_create_lyrics_table;
+mod m20250619_100000_add_track_cue_offsets;
+mod m20250620_110000_add_playlist_import_columns;
+mod m20250621_093000_create_genre_tables;
+mod m20250622_101500_add_track_duplicate_of;
+mod m20250623_090000_add_path_bytes;
+mod m20250624_090000_alter_track_duration_nullable;
+mod m20250624_100000_alter_artist_path_nullable;
+mod m20250624_110000_remove_non_utf8_issues;
+mod m20250624_120000_add_track_acoustic_fingerprint;
+mod m20250624_130000_alter_track_path_index;
+mod m20250624_140000_add_artwork_path_bytes;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -20,6 +43,29 @@ impl MigratorTrait for Migrator {
             Box::new(m20250320_162211_create_track_table::Migration),
             Box::new(m20250510_091903_add_artist_checksum::Migration),
             Box::new(m20250527_113751_alter_track_number::Migration),
+            Box::new(m20250606_101500_add_track_disc_number::Migration),
+            Box::new(m20250606_134200_alter_track_duration::Migration),
+            Box::new(m20250607_091200_add_track_fingerprint::Migration),
+            Box::new(m20250608_143000_add_unique_constraints::Migration),
+            Box::new(m20250610_183000_create_scan_job_table::Migration),
+            Box::new(m20250612_094500_create_scan_issue_table::Migration),
+            Box::new(m20250613_101000_add_track_disc_total::Migration),
+            Box::new(m20250614_120000_create_track_artist_table::Migration),
+            Box::new(m20250615_093000_add_artwork_paths::Migration),
+            Box::new(m20250616_104500_add_track_audio_properties::Migration),
+            Box::new(m20250617_091500_add_replay_gain::Migration),
+            Box::new(m20250618_140000_create_lyrics_table::Migration),
+            Box::new(m20250619_100000_add_track_cue_offsets::Migration),
+            Box::new(m20250620_110000_add_playlist_import_columns::Migration),
+            Box::new(m20250621_093000_create_genre_tables::Migration),
+            Box::new(m20250622_101500_add_track_duplicate_of::Migration),
+            Box::new(m20250623_090000_add_path_bytes::Migration),
+            Box::new(m20250624_090000_alter_track_duration_nullable::Migration),
+            Box::new(m20250624_100000_alter_artist_path_nullable::Migration),
+            Box::new(m20250624_110000_remove_non_utf8_issues::Migration),
+            Box::new(m20250624_120000_add_track_acoustic_fingerprint::Migration),
+            Box::new(m20250624_130000_alter_track_path_index::Migration),
+            Box::new(m20250624_140000_add_artwork_path_bytes::Migration),
         ]
     }
 }
@@ -69,7 +69,8 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Track {
+#[allow(clippy::enum_variant_names)]
+pub enum Track {
     Table,
     ID,
     Title,
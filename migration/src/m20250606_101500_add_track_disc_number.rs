@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(
+                    ColumnDef::new(Track::DiscNumber)
+                        .integer()
+                        .not_null()
+                        .default(1),
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::DiscNumber)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    DiscNumber,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+/// Widens the track duration so it can hold milliseconds
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .modify_column(
+                    ColumnDef::new(Track::Duration)
+                        .big_integer()
+                        .not_null()
+                ).to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .modify_column(
+                    ColumnDef::new(Track::Duration)
+                        .integer()
+                        .not_null()
+                ).to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    Duration
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(
+                    ColumnDef::new(Track::FileSize)
+                        .big_integer()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::ModifiedAt)
+                        .big_integer()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::ContentHash)
+                        .string()
+                        .null(),
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::FileSize)
+                .drop_column(Track::ModifiedAt)
+                .drop_column(Track::ContentHash)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    FileSize,
+    ModifiedAt,
+    ContentHash,
+}
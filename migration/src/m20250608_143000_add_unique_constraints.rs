@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds unique constraints so concurrent scans cannot create duplicate artists, albums or tracks.
+/// Duplicates created by earlier overlapping scans are merged into the oldest row first.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "UPDATE album SET artist_id = duplicate.keep_id
+             FROM (SELECT id, MIN(id) OVER (PARTITION BY name) AS keep_id FROM artist) AS duplicate
+             WHERE album.artist_id = duplicate.id AND duplicate.id <> duplicate.keep_id"
+        ).await?;
+        db.execute_unprepared(
+            "DELETE FROM artist USING artist AS keep
+             WHERE artist.name = keep.name AND artist.id > keep.id"
+        ).await?;
+        db.execute_unprepared(
+            "UPDATE track SET album_id = duplicate.keep_id
+             FROM (SELECT id, MIN(id) OVER (PARTITION BY artist_id, title) AS keep_id FROM album) AS duplicate
+             WHERE track.album_id = duplicate.id AND duplicate.id <> duplicate.keep_id"
+        ).await?;
+        db.execute_unprepared(
+            "DELETE FROM album USING album AS keep
+             WHERE album.artist_id = keep.artist_id AND album.title = keep.title AND album.id > keep.id"
+        ).await?;
+        db.execute_unprepared(
+            "DELETE FROM track USING track AS keep
+             WHERE track.path = keep.path AND track.id > keep.id"
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("UQ_Artist_Name")
+                .table(Artist::Table)
+                .col(Artist::Name)
+                .unique()
+                .to_owned(),
+        ).await?;
+        manager.create_index(
+            Index::create()
+                .name("UQ_Album_Artist_Title")
+                .table(Album::Table)
+                .col(Album::ArtistId)
+                .col(Album::Title)
+                .unique()
+                .to_owned(),
+        ).await?;
+        manager.create_index(
+            Index::create()
+                .name("UQ_Track_Path")
+                .table(Track::Table)
+                .col(Track::Path)
+                .unique()
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(Index::drop().name("UQ_Track_Path").table(Track::Table).to_owned()).await?;
+        manager.drop_index(Index::drop().name("UQ_Album_Artist_Title").table(Album::Table).to_owned()).await?;
+        manager.drop_index(Index::drop().name("UQ_Artist_Name").table(Artist::Table).to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Artist {
+    Table,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum Album {
+    Table,
+    ArtistId,
+    Title,
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    Path,
+}
@@ -0,0 +1,85 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScanJob::Table)
+                    .if_not_exists()
+
+                    .col(
+                        pk_auto(ScanJob::ID)
+                    )
+
+                    .col(
+                        string(ScanJob::State)
+                            .string_len(16)
+                            .not_null()
+                    )
+
+                    .col(
+                        timestamp_with_time_zone(ScanJob::StartedAt)
+                            .not_null()
+                    )
+
+                    .col(
+                        timestamp_with_time_zone_null(ScanJob::FinishedAt)
+                    )
+
+                    .col(integer(ScanJob::ArtistsTotal).default(0))
+                    .col(integer(ScanJob::ArtistsScanned).default(0))
+                    .col(integer(ScanJob::ArtistsAdded).default(0))
+                    .col(integer(ScanJob::ArtistsUpdated).default(0))
+                    .col(integer(ScanJob::ArtistsRemoved).default(0))
+                    .col(integer(ScanJob::AlbumsAdded).default(0))
+                    .col(integer(ScanJob::AlbumsUpdated).default(0))
+                    .col(integer(ScanJob::AlbumsRemoved).default(0))
+                    .col(integer(ScanJob::TracksAdded).default(0))
+                    .col(integer(ScanJob::TracksUpdated).default(0))
+                    .col(integer(ScanJob::TracksRemoved).default(0))
+
+                    .col(
+                        json_binary(ScanJob::Errors)
+                            .not_null()
+                            .default(Expr::cust("'[]'::jsonb"))
+                    )
+
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .drop_table(Table::drop().table(ScanJob::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ScanJob {
+    Table,
+    ID,
+    State,
+    StartedAt,
+    FinishedAt,
+    ArtistsTotal,
+    ArtistsScanned,
+    ArtistsAdded,
+    ArtistsUpdated,
+    ArtistsRemoved,
+    AlbumsAdded,
+    AlbumsUpdated,
+    AlbumsRemoved,
+    TracksAdded,
+    TracksUpdated,
+    TracksRemoved,
+    Errors,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250610_183000_create_scan_job_table::ScanJob;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScanIssue::Table)
+                    .if_not_exists()
+                    .col(
+                        pk_auto(ScanIssue::ID)
+                    )
+
+                    .col(
+                        integer(ScanIssue::ScanJobID)
+                            .not_null()
+                    )
+
+                    .col(
+                        string(ScanIssue::Path)
+                            .not_null()
+                    )
+
+                    .col(
+                        string(ScanIssue::Reason)
+                            .string_len(32)
+                            .not_null()
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_ScanIssue_ScanJob")
+                            .from(ScanIssue::Table, ScanIssue::ScanJobID)
+                            .to(ScanJob::Table, ScanJob::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .drop_table(Table::drop().table(ScanIssue::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ScanIssue {
+    Table,
+    ID,
+    ScanJobID,
+    Path,
+    Reason,
+}
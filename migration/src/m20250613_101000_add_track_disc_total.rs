@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(
+                    ColumnDef::new(Track::DiscTotal)
+                        .integer()
+                        .null(),
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::DiscTotal)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    DiscTotal,
+}
@@ -0,0 +1,115 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250318_133718_create_artist_table::Artist;
+use crate::m20250320_162211_create_track_table::Track;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TrackArtist::Table)
+                    .if_not_exists()
+                    .col(
+                        pk_auto(TrackArtist::ID)
+                    )
+
+                    .col(
+                        integer(TrackArtist::TrackID)
+                            .not_null()
+                    )
+
+                    .col(
+                        integer(TrackArtist::ArtistID)
+                            .not_null()
+                    )
+
+                    .col(
+                        string(TrackArtist::Role)
+                            .string_len(16)
+                            .not_null()
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_TrackArtist_Track")
+                            .from(TrackArtist::Table, TrackArtist::TrackID)
+                            .to(Track::Table, Track::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_TrackArtist_Artist")
+                            .from(TrackArtist::Table, TrackArtist::ArtistID)
+                            .to(Artist::Table, Artist::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("UQ_TrackArtist_Track_Artist_Role")
+                    .table(TrackArtist::Table)
+                    .col(TrackArtist::TrackID)
+                    .col(TrackArtist::ArtistID)
+                    .col(TrackArtist::Role)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Album::Table)
+                    .add_column(
+                        ColumnDef::new(Album::Compilation)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Album::Table)
+                    .drop_column(Album::Compilation)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(TrackArtist::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TrackArtist {
+    Table,
+    ID,
+    TrackID,
+    ArtistID,
+    Role,
+}
+
+#[derive(DeriveIden)]
+enum Album {
+    Table,
+    Compilation,
+}
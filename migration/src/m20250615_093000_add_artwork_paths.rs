@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .add_column(
+                    ColumnDef::new(Album::CoverPath)
+                        .string()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Album::CoverEmbedded)
+                        .boolean()
+                        .not_null()
+                        .default(false),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .add_column(
+                    ColumnDef::new(Artist::ImagePath)
+                        .string()
+                        .null(),
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .drop_column(Artist::ImagePath)
+                .to_owned(),
+        ).await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .drop_column(Album::CoverPath)
+                .drop_column(Album::CoverEmbedded)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Album {
+    Table,
+    CoverPath,
+    CoverEmbedded,
+}
+
+#[derive(DeriveIden)]
+enum Artist {
+    Table,
+    ImagePath,
+}
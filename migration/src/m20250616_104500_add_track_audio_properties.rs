@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(
+                    ColumnDef::new(Track::Codec)
+                        .string()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::Container)
+                        .string()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::Bitrate)
+                        .integer()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::SampleRate)
+                        .integer()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::BitDepth)
+                        .integer()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::Channels)
+                        .integer()
+                        .null(),
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::Codec)
+                .drop_column(Track::Container)
+                .drop_column(Track::Bitrate)
+                .drop_column(Track::SampleRate)
+                .drop_column(Track::BitDepth)
+                .drop_column(Track::Channels)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    Codec,
+    Container,
+    Bitrate,
+    SampleRate,
+    BitDepth,
+    Channels,
+}
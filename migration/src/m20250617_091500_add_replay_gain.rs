@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(
+                    ColumnDef::new(Track::TrackGain)
+                        .double()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::TrackPeak)
+                        .double()
+                        .null(),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .add_column(
+                    ColumnDef::new(Album::AlbumGain)
+                        .double()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Album::AlbumPeak)
+                        .double()
+                        .null(),
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .drop_column(Album::AlbumGain)
+                .drop_column(Album::AlbumPeak)
+                .to_owned(),
+        ).await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::TrackGain)
+                .drop_column(Track::TrackPeak)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+enum Track {
+    Table,
+    TrackGain,
+    TrackPeak,
+}
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+enum Album {
+    Table,
+    AlbumGain,
+    AlbumPeak,
+}
@@ -0,0 +1,70 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250320_162211_create_track_table::Track;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Lyrics::Table)
+                    .if_not_exists()
+                    .col(
+                        pk_auto(Lyrics::ID)
+                    )
+
+                    .col(
+                        integer(Lyrics::TrackID)
+                            .not_null()
+                            .unique_key()
+                    )
+
+                    .col(
+                        text(Lyrics::Content)
+                            .not_null()
+                    )
+
+                    .col(
+                        boolean(Lyrics::Synced)
+                            .not_null()
+                    )
+
+                    .col(
+                        string(Lyrics::Source)
+                            .string_len(16)
+                            .not_null()
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_Lyrics_Track")
+                            .from(Lyrics::Table, Lyrics::TrackID)
+                            .to(Track::Table, Track::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Lyrics::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Lyrics {
+    Table,
+    ID,
+    TrackID,
+    Content,
+    Synced,
+    Source,
+}
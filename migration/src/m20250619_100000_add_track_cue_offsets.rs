@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+/// Tracks split from a CUE sheet share the path of their file, so tracks are unique by path and CUE track number
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(
+                    ColumnDef::new(Track::CueTrack)
+                        .integer()
+                        .not_null()
+                        .default(0),
+                )
+                .add_column(
+                    ColumnDef::new(Track::StartOffset)
+                        .big_integer()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Track::EndOffset)
+                        .big_integer()
+                        .null(),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.drop_index(Index::drop().name("UQ_Track_Path").table(Track::Table).to_owned()).await?;
+        manager.create_index(
+            Index::create()
+                .name("UQ_Track_Path_CueTrack")
+                .table(Track::Table)
+                .col(Track::Path)
+                .col(Track::CueTrack)
+                .unique()
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DELETE FROM track WHERE cue_track <> 0").await?;
+
+        manager.drop_index(Index::drop().name("UQ_Track_Path_CueTrack").table(Track::Table).to_owned()).await?;
+        manager.create_index(
+            Index::create()
+                .name("UQ_Track_Path")
+                .table(Track::Table)
+                .col(Track::Path)
+                .unique()
+                .to_owned(),
+        ).await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::CueTrack)
+                .drop_column(Track::StartOffset)
+                .drop_column(Track::EndOffset)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+enum Track {
+    Table,
+    Path,
+    CueTrack,
+    StartOffset,
+    EndOffset,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+/// Playlists imported from playlist files in the library are identified by their file and belong to no user
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Playlist::Table)
+                .modify_column(
+                    ColumnDef::new(Playlist::UserID)
+                        .integer()
+                        .null(),
+                )
+                .add_column(
+                    ColumnDef::new(Playlist::Path)
+                        .string()
+                        .null()
+                        .unique_key(),
+                )
+                .add_column(
+                    array(Playlist::UnresolvedEntries, ColumnType::Text)
+                        .not_null()
+                        .default("ARRAY[]::text[]"),
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DELETE FROM playlist WHERE user_id IS NULL").await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Playlist::Table)
+                .drop_column(Playlist::Path)
+                .drop_column(Playlist::UnresolvedEntries)
+                .modify_column(
+                    ColumnDef::new(Playlist::UserID)
+                        .integer()
+                        .not_null(),
+                )
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Playlist {
+    Table,
+    UserID,
+    Path,
+    UnresolvedEntries,
+}
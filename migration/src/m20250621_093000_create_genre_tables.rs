@@ -0,0 +1,203 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250319_153237_create_album_table::Album;
+use crate::m20250320_162211_create_track_table::Track;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Genre::Table)
+                    .if_not_exists()
+                    .col(
+                        pk_auto(Genre::ID)
+                    )
+
+                    .col(
+                        string(Genre::Name)
+                            .not_null()
+                    )
+
+                    .col(
+                        string(Genre::Key)
+                            .not_null()
+                    )
+
+                    .col(
+                        string(Genre::Kind)
+                            .string_len(16)
+                            .not_null()
+                    )
+
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("UQ_Genre_Key_Kind")
+                    .table(Genre::Table)
+                    .col(Genre::Key)
+                    .col(Genre::Kind)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TrackGenre::Table)
+                    .if_not_exists()
+                    .col(
+                        pk_auto(TrackGenre::ID)
+                    )
+
+                    .col(
+                        integer(TrackGenre::TrackID)
+                            .not_null()
+                    )
+
+                    .col(
+                        integer(TrackGenre::GenreID)
+                            .not_null()
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_TrackGenre_Track")
+                            .from(TrackGenre::Table, TrackGenre::TrackID)
+                            .to(Track::Table, Track::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_TrackGenre_Genre")
+                            .from(TrackGenre::Table, TrackGenre::GenreID)
+                            .to(Genre::Table, Genre::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("UQ_TrackGenre_Track_Genre")
+                    .table(TrackGenre::Table)
+                    .col(TrackGenre::TrackID)
+                    .col(TrackGenre::GenreID)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AlbumGenre::Table)
+                    .if_not_exists()
+                    .col(
+                        pk_auto(AlbumGenre::ID)
+                    )
+
+                    .col(
+                        integer(AlbumGenre::AlbumID)
+                            .not_null()
+                    )
+
+                    .col(
+                        integer(AlbumGenre::GenreID)
+                            .not_null()
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_AlbumGenre_Album")
+                            .from(AlbumGenre::Table, AlbumGenre::AlbumID)
+                            .to(Album::Table, Album::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_AlbumGenre_Genre")
+                            .from(AlbumGenre::Table, AlbumGenre::GenreID)
+                            .to(Genre::Table, Genre::ID)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("UQ_AlbumGenre_Album_Genre")
+                    .table(AlbumGenre::Table)
+                    .col(AlbumGenre::AlbumID)
+                    .col(AlbumGenre::GenreID)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Forget the fingerprints, so the next scan reads the tags of every track again and picks up their genres
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE artist SET checksum = NULL").await?;
+        db.execute_unprepared("UPDATE track SET modified_at = NULL").await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AlbumGenre::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(TrackGenre::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Genre::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Genre {
+    Table,
+    ID,
+    Name,
+    /// Name folded to lowercase letters and digits, so `Hip-Hop` and `hip hop` are the same genre
+    Key,
+    Kind,
+}
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+enum TrackGenre {
+    Table,
+    ID,
+    TrackID,
+    GenreID,
+}
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+enum AlbumGenre {
+    Table,
+    ID,
+    AlbumID,
+    GenreID,
+}
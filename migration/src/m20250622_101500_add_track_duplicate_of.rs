@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+/// Lesser copies of a track point at the preferred version, so clients can hide them
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(
+                    ColumnDef::new(Track::DuplicateOf)
+                        .integer()
+                        .null(),
+                )
+                .add_foreign_key(
+                    TableForeignKey::new()
+                        .name("FK_Track_DuplicateOf")
+                        .from_tbl(Track::Table)
+                        .from_col(Track::DuplicateOf)
+                        .to_tbl(Track::Table)
+                        .to_col(Track::ID)
+                        .on_delete(ForeignKeyAction::SetNull),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("IDX_Track_DuplicateOf")
+                .table(Track::Table)
+                .col(Track::DuplicateOf)
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_foreign_key(Alias::new("FK_Track_DuplicateOf"))
+                .drop_column(Track::DuplicateOf)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    ID,
+    DuplicateOf,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+/// Paths that are not valid UTF-8 keep their raw bytes next to the lossy text in `path`
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .add_column(ColumnDef::new(Artist::PathBytes).binary().null())
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .add_column(ColumnDef::new(Album::PathBytes).binary().null())
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(ColumnDef::new(Track::PathBytes).binary().null())
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::PathBytes)
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .drop_column(Album::PathBytes)
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .drop_column(Artist::PathBytes)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Artist {
+    Table,
+    PathBytes,
+}
+
+#[derive(DeriveIden)]
+enum Album {
+    Table,
+    PathBytes,
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    PathBytes,
+}
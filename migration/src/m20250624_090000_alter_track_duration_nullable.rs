@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+/// Tracks whose duration is unknown store no duration instead of the placeholder older scans stored
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .modify_column(
+                    ColumnDef::new(Track::Duration)
+                        .big_integer()
+                        .null()
+                ).to_owned(),
+        ).await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE track SET duration = NULL WHERE duration = 1337").await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE track SET duration = 1337 WHERE duration IS NULL").await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .modify_column(
+                    ColumnDef::new(Track::Duration)
+                        .big_integer()
+                        .not_null()
+                ).to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    Duration
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+/// Artists only known from the tags of other artists' albums have no directory of their own, so no path.
+/// Older scans stored the directory of the album they were found in, those paths are cleared.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .modify_column(
+                    ColumnDef::new(Artist::Path)
+                        .string()
+                        .null()
+                ).to_owned(),
+        ).await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE artist SET path = NULL, path_bytes = NULL WHERE right(path, length(name) + 1) <> '/' || name").await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE artist SET path = '' WHERE path IS NULL").await?;
+
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .modify_column(
+                    ColumnDef::new(Artist::Path)
+                        .string()
+                        .not_null()
+                ).to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Artist {
+    Table,
+    Path,
+}
@@ -0,0 +1,18 @@
+use sea_orm_migration::prelude::*;
+
+/// Names that are not valid UTF-8 are scanned, so they are no longer reported as scan issues
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DELETE FROM scan_issue WHERE reason = 'non_utf8_name'").await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+/// Chromaprint fingerprints are kept with the size and modification time of the file they were computed from,
+/// so only new and changed files are fingerprinted again
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .add_column(ColumnDef::new(Track::AcousticFingerprint).binary().null())
+                .add_column(ColumnDef::new(Track::AcousticFingerprintKey).string().null())
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Track::Table)
+                .drop_column(Track::AcousticFingerprint)
+                .drop_column(Track::AcousticFingerprintKey)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Track {
+    Table,
+    AcousticFingerprint,
+    AcousticFingerprintKey,
+}
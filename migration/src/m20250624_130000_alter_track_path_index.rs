@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+/// Paths that are not valid UTF-8 can share their lossy text, so tracks are unique by their path bytes as well
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(Index::drop().name("UQ_Track_Path_CueTrack").table(Track::Table).to_owned()).await?;
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"CREATE UNIQUE INDEX "UQ_Track_Path_PathBytes_CueTrack" ON track (path, coalesce(path_bytes, ''::bytea), cue_track)"#
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(Index::drop().name("UQ_Track_Path_PathBytes_CueTrack").table(Track::Table).to_owned()).await?;
+        manager.create_index(
+            Index::create()
+                .name("UQ_Track_Path_CueTrack")
+                .table(Track::Table)
+                .col(Track::Path)
+                .col(Track::CueTrack)
+                .unique()
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+enum Track {
+    Table,
+    Path,
+    CueTrack,
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+/// Artwork paths that are not valid UTF-8 keep their raw bytes next to the lossy text, like the other paths
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .add_column(ColumnDef::new(Album::CoverPathBytes).binary().null())
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .add_column(ColumnDef::new(Artist::ImagePathBytes).binary().null())
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Artist::Table)
+                .drop_column(Artist::ImagePathBytes)
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Album::Table)
+                .drop_column(Album::CoverPathBytes)
+                .to_owned(),
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Album {
+    Table,
+    CoverPathBytes,
+}
+
+#[derive(DeriveIden)]
+enum Artist {
+    Table,
+    ImagePathBytes,
+}
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+use lofty::file::TaggedFileExt;
+use lofty::picture::{Picture, PictureType};
+use lofty::probe::Probe;
+use service::album::AlbumAlter;
+use service::artist::ArtistAlter;
+use service::path::stored_path;
+use crate::ignore_rules::IgnoreRules;
+use crate::{is_audio_file, Scanner};
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "webp", "gif", "bmp"];
+/// File names of album covers, in order of preference
+const COVER_NAMES: [&str; 3] = ["cover", "folder", "front"];
+const ARTIST_IMAGE_NAMES: [&str; 2] = ["artist", "folder"];
+
+/// Picture embedded in the tags of an audio file
+pub struct EmbeddedPicture {
+    pub data: Vec<u8>,
+    pub mime_type: Option<String>,
+}
+
+impl Scanner {
+    /// Records the cover of every album and the image of every artist, or only of those in the directories when given.
+    /// Album covers are image files like `cover.jpg` in the album directory,
+    /// falling back to the picture embedded in the first track. Artist images are files like `artist.jpg`.
+    pub(crate) async fn refresh_artwork(&self, directories: Option<&[PathBuf]>) {
+        let in_directories = |path: &Path| directories.is_none_or(|directories| directories.iter().any(|directory| path.starts_with(directory)));
+        for album in self.album_service.get_all().await.unwrap() {
+            let album_path = stored_path(&album.path, album.path_bytes.as_deref());
+            if !in_directories(&album_path) {
+                continue;
+            }
+            let (cover_path, cover_embedded) = match find_album_cover(&album_path, &self.ignore_rules) {
+                Some((path, embedded)) => (Some(path), embedded),
+                None => (None, false),
+            };
+            let stored_cover = album.cover_path.as_deref().map(|path| stored_path(path, album.cover_path_bytes.as_deref()));
+            if cover_path == stored_cover && cover_embedded == album.cover_embedded {
+                continue;
+            }
+            self.album_service.alter(album.id, AlbumAlter {
+                cover_path: Some(cover_path),
+                cover_embedded: Some(cover_embedded),
+                ..Default::default()
+            }).await.unwrap();
+        }
+
+        for artist in self.artist_service.get_all().await.unwrap() {
+            let Some(artist_path) = artist.path.as_deref().map(|path| stored_path(path, artist.path_bytes.as_deref())) else {
+                continue;
+            };
+            if !in_directories(&artist_path) {
+                continue;
+            }
+            let image_path = find_image(&artist_path, &ARTIST_IMAGE_NAMES, &self.ignore_rules);
+            let stored_image = artist.image_path.as_deref().map(|path| stored_path(path, artist.image_path_bytes.as_deref()));
+            if image_path == stored_image {
+                continue;
+            }
+            self.artist_service.alter(artist.id, ArtistAlter {
+                image_path: Some(image_path),
+                ..Default::default()
+            }).await.unwrap();
+        }
+    }
+}
+
+/// Finds the cover image in the album directory, or the first track with an embedded picture.
+/// Returns the path and whether the picture is embedded in that file.
+fn find_album_cover(path: &Path, rules: &IgnoreRules) -> Option<(PathBuf, bool)> {
+    if let Some(cover) = find_image(path, &COVER_NAMES, rules) {
+        return Some((cover, false))
+    }
+    let first_track = rules.walk(path).min_depth(1).max_depth(2).sort_by_file_name().into_iter()
+        .filter_entry(|entry| !rules.skips(entry))
+        .filter_map(|e| e.ok())
+        .find(|entry| entry.file_type().is_file() && is_audio_file(entry.path()))?;
+    read_embedded_picture(first_track.path())?;
+    Some((first_track.path().to_path_buf(), true))
+}
+
+/// Finds an image in the directory named after one of the names, case-insensitive and in order of the names
+fn find_image(path: &Path, names: &[&str], rules: &IgnoreRules) -> Option<PathBuf> {
+    if !path.is_dir() {
+        return None
+    }
+    let images: Vec<PathBuf> = rules.walk(path).min_depth(1).max_depth(1).sort_by_file_name().into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.path().to_path_buf())
+        .filter(|path| {
+            let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+            IMAGE_EXTENSIONS.contains(&extension.as_str())
+        })
+        .collect();
+    names.iter().find_map(|name| {
+        images.iter()
+            .find(|image| image.file_stem().and_then(|s| s.to_str()).is_some_and(|stem| stem.eq_ignore_ascii_case(name)))
+            .cloned()
+    })
+}
+
+/// Reads the front cover embedded in the tags of the audio file, or the first picture when none is marked as front cover
+pub fn read_embedded_picture(path: &Path) -> Option<EmbeddedPicture> {
+    let tagged_file = Probe::open(path).ok()?.guess_file_type().ok()?.read().ok()?;
+    let pictures: Vec<&Picture> = tagged_file.tags().iter()
+        .flat_map(|tag| tag.pictures())
+        .collect();
+    let picture = pictures.iter()
+        .find(|picture| picture.pic_type() == PictureType::CoverFront)
+        .or(pictures.first())?;
+    Some(EmbeddedPicture {
+        data: picture.data().to_vec(),
+        mime_type: picture.mime_type().map(|mime_type| mime_type.as_str().to_string()),
+    })
+}
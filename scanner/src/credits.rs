@@ -0,0 +1,76 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use entities::sea_orm_active_enums::ArtistRole;
+
+/// Name used for the album artist of compilations
+pub(crate) const VARIOUS_ARTISTS: &str = "Various Artists";
+
+/// Artists credited on a track, by name
+#[derive(Default)]
+pub(crate) struct TrackCredits {
+    pub credits: Vec<(String, ArtistRole)>,
+}
+
+impl TrackCredits {
+    /// Collects the credits from the track artist, composer and remixer tags.
+    /// Featured artists are split off the track artist and the title, like `Artist feat. Other` or `Title (ft. Other)`.
+    pub fn new(artist: Option<&str>, title: &str, composer: Option<&str>, remixer: Option<&str>) -> Self {
+        let mut credits = TrackCredits::default();
+        if let Some(artist) = artist {
+            let (primary, featured) = split_featured(artist);
+            for name in split_names(primary) {
+                credits.add(name, ArtistRole::Primary);
+            }
+            for name in featured {
+                credits.add(name, ArtistRole::Featured);
+            }
+        }
+        for name in split_featured(title).1 {
+            credits.add(name, ArtistRole::Featured);
+        }
+        for name in composer.map(split_names).unwrap_or_default() {
+            credits.add(name, ArtistRole::Composer);
+        }
+        for name in remixer.map(split_names).unwrap_or_default() {
+            credits.add(name, ArtistRole::Remixer);
+        }
+        credits
+    }
+
+    pub fn has_primary(&self) -> bool {
+        self.credits.iter().any(|(_, role)| *role == ArtistRole::Primary)
+    }
+
+    pub fn add(&mut self, name: &str, role: ArtistRole) {
+        let name = name.trim();
+        if name.is_empty() || self.credits.iter().any(|(n, r)| n.eq_ignore_ascii_case(name) && *r == role) {
+            return
+        }
+        self.credits.push((name.to_string(), role));
+    }
+}
+
+pub(crate) fn is_various_artists(name: &str) -> bool {
+    name.eq_ignore_ascii_case(VARIOUS_ARTISTS)
+}
+
+/// Splits `Artist feat. Other & Another` into the artist and the featured artists
+fn split_featured(value: &str) -> (&str, Vec<&str>) {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\s*[(\[]?\b(?:feat\.?|ft\.|featuring)\s+([^)\]]+)[)\]]?").unwrap());
+    let Some(result) = RE.captures(value) else {
+        return (value, vec![])
+    };
+    let featured = result.get(1).map(|x| split_featured_names(x.as_str())).unwrap_or_default();
+    (&value[..result.get(0).unwrap().start()], featured)
+}
+
+/// Multiple names in a single tag are separated by `;`
+fn split_names(value: &str) -> Vec<&str> {
+    value.split(';').map(|name| name.trim()).filter(|name| !name.is_empty()).collect()
+}
+
+/// Featured artists are usually listed as `A, B & C`
+fn split_featured_names(value: &str) -> Vec<&str> {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s*(?:,|&|;|\band\b)\s*").unwrap());
+    RE.split(value).map(|name| name.trim()).filter(|name| !name.is_empty()).collect()
+}
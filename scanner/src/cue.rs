@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::DirEntry;
+use entities::track::Model;
+use crate::credits::TrackCredits;
+use crate::duration::read_container_duration;
+use crate::fingerprint::Fingerprint;
+use crate::genres::{read_styles, track_genres};
+use crate::metadata::read_metadata;
+use crate::ignore_rules::IgnoreRules;
+use crate::{decode_text, disc_folder_number, track_properties, TrackInfo, TrackKey};
+
+/// CUE sheets count time in frames of 1/75 second
+const FRAMES_PER_SECOND: i64 = 75;
+
+/// A CUE sheet describing how single-file rips are split into tracks
+pub(crate) struct CueSheet {
+    /// Directory of the CUE sheet, the files it references are relative to it
+    dir: PathBuf,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub year: Option<i32>,
+    genre: Option<String>,
+    files: Vec<CueFile>,
+}
+
+struct CueFile {
+    name: String,
+    tracks: Vec<CueTrack>,
+}
+
+struct CueTrack {
+    number: i32,
+    title: Option<String>,
+    performer: Option<String>,
+    songwriter: Option<String>,
+    /// Start of the track in milliseconds, at its `INDEX 01`
+    start: i64,
+    /// End of the track in milliseconds, None for the last track of the file
+    end: Option<i64>,
+}
+
+impl CueTrack {
+    fn title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| format!("Track {:02}", self.number))
+    }
+}
+
+/// The CUE sheets of an album directory and its disc folders
+pub(crate) struct CueSheets(Vec<CueSheet>);
+
+impl CueSheets {
+    pub fn read(path: &Path, rules: &IgnoreRules) -> Self {
+        let sheets = rules.walk(path).min_depth(1).max_depth(2).sort_by_file_name().into_iter()
+            .filter_entry(|entry| !rules.skips(entry))
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.depth() == 1 || entry.path().parent().and_then(disc_folder_number).is_some())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| entry.path().extension().and_then(|s| s.to_str()).is_some_and(|extension| extension.eq_ignore_ascii_case("cue")))
+            .filter_map(|entry| {
+                let content = fs::read(entry.path()).ok()?;
+                Some(parse_cue(&decode_text(content), entry.path().parent()?))
+            })
+            .collect();
+        CueSheets(sheets)
+    }
+
+    pub fn first(&self) -> Option<&CueSheet> {
+        self.0.first()
+    }
+
+    /// Finds the CUE sheet splitting the audio file into several tracks.
+    /// Files are matched by name, or by name without extension for rips converted after the CUE sheet was written.
+    fn find(&self, path: &Path) -> Option<(&CueSheet, &CueFile)> {
+        let name = path.file_name()?.to_string_lossy();
+        let stem = path.file_stem()?.to_string_lossy();
+        self.0.iter()
+            .filter(|sheet| Some(sheet.dir.as_path()) == path.parent())
+            .flat_map(|sheet| sheet.files.iter().map(move |file| (sheet, file)))
+            .filter(|(_, file)| file.tracks.len() > 1)
+            .find(|(_, file)| {
+                let file_stem = Path::new(&file.name).file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+                file.name.eq_ignore_ascii_case(&name) || file_stem.eq_ignore_ascii_case(&stem)
+            })
+    }
+
+    pub fn splits(&self, path: &Path) -> bool {
+        self.find(path).is_some()
+    }
+
+    /// Checks whether the tracks stored for the file still match the file on disk and its CUE sheet
+    pub fn unchanged(&self, path: &Path, stored_tracks: &HashMap<TrackKey, Model>, fingerprint: &Fingerprint) -> bool {
+        let Some((_, file)) = self.find(path) else {
+            return false
+        };
+        let stored_count = stored_tracks.keys().filter(|(stored_path, _)| stored_path == path).count();
+        stored_count == file.tracks.len() && file.tracks.iter().all(|track| {
+            stored_tracks.get(&(path.to_path_buf(), track.number)).is_some_and(|stored| {
+                fingerprint.matches(stored) && stored.title == track.title()
+                    && stored.start_offset == Some(track.start) && stored.end_offset == track.end
+            })
+        })
+    }
+
+    /// Splits the audio file into a track per CUE track.
+    /// Titles, performers and the genre come from the CUE sheet, everything else from the tags of the file.
+    pub fn track_info(&self, entry: &DirEntry) -> Vec<TrackInfo> {
+        let Some((sheet, file)) = self.find(entry.path()) else {
+            return vec![]
+        };
+        let metadata = read_metadata(entry.path()).unwrap_or_default();
+        let file_duration = metadata.duration.or_else(|| read_container_duration(entry.path()));
+        let disc_number = metadata.disc_number
+            .or_else(|| entry.path().parent().and_then(disc_folder_number))
+            .unwrap_or(1);
+        let properties = track_properties(entry.path(), metadata.properties);
+        let genres = match &sheet.genre {
+            Some(genre) => vec![genre.clone()],
+            None => metadata.genres,
+        };
+        let genres = track_genres(genres, metadata.moods, read_styles(entry.path()));
+
+        file.tracks.iter().map(|track| {
+            let title = track.title();
+            let artist = track.performer.as_deref()
+                .or(sheet.performer.as_deref())
+                .or(metadata.artist.as_deref());
+            let composer = track.songwriter.as_deref().or(metadata.composer.as_deref());
+            // The last track ends with the file, its duration is unknown when the duration of the file is
+            let end = track.end.or(file_duration);
+            TrackInfo {
+                credits: TrackCredits::new(artist, &title, composer, None),
+                title,
+                track_number: track.number,
+                disc_number,
+                disc_total: metadata.disc_total,
+                duration: end.map(|end| (end - track.start).max(0)),
+                properties: properties.clone(),
+                track_gain: None,
+                track_peak: None,
+                lyrics: None,
+                genres: genres.clone(),
+                cue_track: track.number,
+                start_offset: Some(track.start),
+                end_offset: track.end,
+            }
+        }).collect()
+    }
+}
+
+fn parse_cue(content: &str, dir: &Path) -> CueSheet {
+    let mut sheet = CueSheet { dir: dir.to_path_buf(), title: None, performer: None, year: None, genre: None, files: Vec::new() };
+    for line in content.lines() {
+        let line = line.trim();
+        let (command, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let arguments = arguments.trim();
+        let file = sheet.files.last_mut();
+        let track = file.and_then(|file| file.tracks.last_mut());
+        match command.to_uppercase().as_str() {
+            "FILE" => sheet.files.push(CueFile { name: file_name(arguments), tracks: Vec::new() }),
+            "TRACK" => {
+                let number = arguments.split_whitespace().next().and_then(|number| number.parse().ok()).unwrap_or(0);
+                if let Some(file) = sheet.files.last_mut().filter(|_| number > 0) {
+                    file.tracks.push(CueTrack { number, title: None, performer: None, songwriter: None, start: -1, end: None });
+                }
+            }
+            "TITLE" => match track {
+                Some(track) => track.title = unquote(arguments),
+                None => sheet.title = unquote(arguments),
+            },
+            "PERFORMER" => match track {
+                Some(track) => track.performer = unquote(arguments),
+                None => sheet.performer = unquote(arguments),
+            },
+            "SONGWRITER" => if let Some(track) = track {
+                track.songwriter = unquote(arguments);
+            },
+            "INDEX" => {
+                let mut parts = arguments.split_whitespace();
+                if let (Some(track), Some("01"), Some(time)) = (track, parts.next(), parts.next()) {
+                    track.start = parse_time(time).unwrap_or(-1);
+                }
+            }
+            "REM" => {
+                let (key, value) = arguments.split_once(char::is_whitespace).unwrap_or((arguments, ""));
+                if key.eq_ignore_ascii_case("DATE") {
+                    sheet.year = value.trim().trim_matches('"').get(..4).and_then(|year| year.parse().ok());
+                } else if key.eq_ignore_ascii_case("GENRE") {
+                    sheet.genre = Some(value.trim().trim_matches('"').to_string()).filter(|genre| !genre.is_empty());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for file in &mut sheet.files {
+        file.tracks.retain(|track| track.start >= 0);
+        let starts: Vec<i64> = file.tracks.iter().map(|track| track.start).collect();
+        for (track, next_start) in file.tracks.iter_mut().zip(starts.iter().skip(1).map(Some).chain([None])) {
+            track.end = next_start.copied();
+        }
+    }
+    sheet
+}
+
+/// Reads the file name of a `FILE "name" TYPE` line, the name is only quoted when it contains spaces
+fn file_name(arguments: &str) -> String {
+    match arguments.strip_prefix('"').and_then(|rest| rest.split_once('"')) {
+        Some((name, _)) => name.to_string(),
+        None => arguments.split_whitespace().next().unwrap_or_default().to_string(),
+    }
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches('"').trim();
+    Some(value.to_string()).filter(|value| !value.is_empty())
+}
+
+/// Parses a `mm:ss:ff` time into milliseconds
+fn parse_time(time: &str) -> Option<i64> {
+    let mut parts = time.split(':').map(|part| part.parse::<i64>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    Some((minutes * 60 + seconds) * 1000 + frames * 1000 / FRAMES_PER_SECOND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = r#"REM GENRE "Progressive Rock"
+REM DATE 1973
+PERFORMER "Pink Floyd"
+TITLE "The Dark Side of the Moon"
+FILE "The Dark Side of the Moon.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Speak to Me"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Breathe"
+    PERFORMER "David Gilmour"
+    INDEX 00 01:06:50
+    INDEX 01 01:07:30
+  TRACK 03 AUDIO
+    INDEX 01 03:50:37
+"#;
+
+    #[test]
+    fn parses_the_sheet_and_its_tracks() {
+        let sheet = parse_cue(SHEET, Path::new("/music/Pink Floyd/The Dark Side of the Moon"));
+        assert_eq!(sheet.title.as_deref(), Some("The Dark Side of the Moon"));
+        assert_eq!(sheet.performer.as_deref(), Some("Pink Floyd"));
+        assert_eq!(sheet.year, Some(1973));
+        assert_eq!(sheet.genre.as_deref(), Some("Progressive Rock"));
+        assert_eq!(sheet.files.len(), 1);
+        assert_eq!(sheet.files[0].name, "The Dark Side of the Moon.flac");
+
+        let tracks = &sheet.files[0].tracks;
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(tracks[1].title(), "Breathe");
+        assert_eq!(tracks[1].performer.as_deref(), Some("David Gilmour"));
+        assert_eq!(tracks[2].title(), "Track 03");
+    }
+
+    #[test]
+    fn tracks_start_at_index_01_and_end_at_the_next_track() {
+        let sheet = parse_cue(SHEET, Path::new("/music"));
+        let tracks = &sheet.files[0].tracks;
+        assert_eq!((tracks[0].start, tracks[0].end), (0, Some(67_400)));
+        assert_eq!((tracks[1].start, tracks[1].end), (67_400, Some(230_493)));
+        assert_eq!((tracks[2].start, tracks[2].end), (230_493, None));
+    }
+
+    #[test]
+    fn reads_unquoted_file_names() {
+        let sheet = parse_cue("FILE album.flac WAVE\n  TRACK 01 AUDIO\n    INDEX 01 00:00:00\n", Path::new("/music"));
+        assert_eq!(sheet.files[0].name, "album.flac");
+        assert_eq!(sheet.files[0].tracks.len(), 1);
+    }
+
+    #[test]
+    fn skips_tracks_without_index_01() {
+        let sheet = parse_cue("FILE \"a b.wav\" WAVE\n  TRACK 01 AUDIO\n    INDEX 00 00:00:00\n  TRACK 02 AUDIO\n    INDEX 01 00:10:00\n", Path::new("/music"));
+        let tracks = &sheet.files[0].tracks;
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].number, 2);
+    }
+}
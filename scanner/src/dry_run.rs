@@ -0,0 +1,34 @@
+use std::path::Path;
+use crate::cue::CueSheets;
+use crate::ignore_rules::IgnoreRules;
+use crate::{extract_track_info, list_directories, list_tracks, read_album_info, ScanStats, Scanner};
+
+impl Scanner {
+    /// Walks the whole library the way a scan would, without writing to the database.
+    /// Every artist is checked regardless of its checksum, so the returned stats hold every issue in the library.
+    /// The dry run reads the ignore files and symlinks into rules of its own, a running scan keeps its rules.
+    pub fn dry_run(&self) -> ScanStats {
+        let stats = ScanStats::default();
+        let ignore_rules = IgnoreRules::new(Path::new(&self.library_path), &self.options.exclude_globs, self.options.follow_symlinks);
+        ignore_rules.index_aliases();
+        for artist in list_directories(Path::new(&self.library_path), &ignore_rules, &stats) {
+            ScanStats::add(&stats.artists_total, 1);
+            for album in list_directories(artist.path(), &ignore_rules, &stats) {
+                if read_album_info(album.path(), &self.options.templates, &ignore_rules, &stats).is_none() {
+                    continue
+                }
+                let cue_sheets = CueSheets::read(album.path(), &ignore_rules);
+                for track in list_tracks(album.path(), &ignore_rules, &stats) {
+                    if cue_sheets.splits(track.path()) {
+                        continue
+                    }
+                    if let Err(reason) = extract_track_info(&track, &self.options.templates) {
+                        stats.issue(track.path(), reason);
+                    }
+                }
+            }
+            ScanStats::add(&stats.artists_scanned, 1);
+        }
+        stats
+    }
+}
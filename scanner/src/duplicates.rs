@@ -0,0 +1,372 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use futures::stream::{self, StreamExt};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use entities::sea_orm_active_enums::ArtistRole;
+use entities::track;
+use service::path::stored_path;
+use service::track::LOSSLESS_CODECS;
+use crate::{ScanStats, Scanner};
+
+/// Bracketed remaster and featuring notes, `Battery (Remastered)` and `Battery` are the same song
+static TITLE_NOTE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\s*[(\[][^)\]]*(remaster|feat\.|ft\.|featuring)[^)\]]*[)\]]").unwrap());
+/// Remaster notes after a dash, like `Battery - 2016 Remaster`
+static TITLE_SUFFIX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\s+-\s+(\d{4}\s+)?remaster(ed)?\b.*$").unwrap());
+
+/// Share of equal bits above which two Chromaprint fingerprints are the same recording.
+/// Unrelated recordings share about half of their bits.
+const ACOUSTIC_MATCH: f64 = 0.85;
+/// Fingerprint items the recordings may be shifted by, about 0.12 seconds each
+const ACOUSTIC_MAX_SHIFT: isize = 8;
+/// Seconds of audio fingerprinted from the start of every track
+const ACOUSTIC_LENGTH: u32 = 120;
+
+impl Scanner {
+    /// Groups tracks that are copies of the same song, like an MP3 and a FLAC rip, or the album and the compilation version.
+    /// Tracks are copies when their normalized title and primary artists are the same and their durations are within
+    /// `ScannerOptions::duplicate_tolerance` of each other. With `ScannerOptions::acoustic_fingerprints`,
+    /// copies are confirmed by comparing Chromaprint fingerprints, tracks that cannot be fingerprinted are matched by their tags.
+    /// Fingerprints are stored, so only new and changed files are fingerprinted again.
+    /// The best copy of every group is preferred, the others point at it.
+    pub(crate) async fn detect_duplicates(&self, stats: &ScanStats) {
+        let tracks = self.track_service.get_all().await.unwrap();
+        let albums: HashMap<i32, (bool, i32)> = self.album_service.get_all().await.unwrap()
+            .into_iter()
+            .map(|album| (album.id, (album.compilation, album.artist_id)))
+            .collect();
+        let mut primary_artists: HashMap<i32, Vec<i32>> = HashMap::new();
+        for credit in self.track_artist_service.get_all().await.unwrap() {
+            if credit.role == ArtistRole::Primary {
+                primary_artists.entry(credit.track_id).or_default().push(credit.artist_id);
+            }
+        }
+
+        let mut candidates: HashMap<(String, Vec<i32>), Vec<track::Model>> = HashMap::new();
+        for track in tracks.into_iter().filter(|track| track.duration.is_some_and(|duration| duration > 0)) {
+            let mut artists = primary_artists.remove(&track.id)
+                .or_else(|| albums.get(&track.album_id).map(|(_, artist_id)| vec![*artist_id]))
+                .unwrap_or_default();
+            artists.sort();
+            artists.dedup();
+            candidates.entry((normalize_title(&track.title), artists)).or_default().push(track);
+        }
+
+        let clusters: Vec<Vec<track::Model>> = candidates.into_iter()
+            .filter(|(_, tracks)| tracks.len() > 1)
+            .flat_map(|(_, mut tracks)| {
+                tracks.sort_by_key(|track| track.duration);
+                split_by_duration(tracks, self.options.duplicate_tolerance)
+            })
+            .filter(|cluster| cluster.len() > 1)
+            .collect();
+        let groups: Vec<Vec<track::Model>> = match self.options.acoustic_fingerprints {
+            true => {
+                let fingerprints = self.acoustic_fingerprints(clusters.iter().flatten(), stats).await;
+                if stats.is_cancelled() {
+                    return
+                }
+                clusters.into_iter()
+                    .flat_map(|cluster| confirm_acoustically(cluster, &fingerprints))
+                    .filter(|group| group.len() > 1)
+                    .collect()
+            }
+            false => clusters,
+        };
+
+        let groups: Vec<(i32, Vec<i32>)> = groups.into_iter().map(|mut group| {
+            group.sort_by_key(|track| Reverse(preference(track, albums.get(&track.album_id).is_some_and(|(compilation, _)| *compilation))));
+            let preferred = group.remove(0);
+            (preferred.id, group.into_iter().map(|track| track.id).collect())
+        }).collect();
+        println!("Found {} songs with several copies", groups.len());
+        self.track_service.replace_duplicates(groups).await.unwrap();
+    }
+
+    /// Reads the Chromaprint fingerprint of every track, keyed by track id.
+    /// Stored fingerprints are used while the file keeps its size and modification time,
+    /// other files are fingerprinted by up to `ScannerOptions::workers` fpcalc processes at the same time.
+    /// Tracks split from a CUE sheet and files fpcalc cannot read have no fingerprint.
+    async fn acoustic_fingerprints(&self, tracks: impl Iterator<Item = &track::Model>, stats: &ScanStats) -> HashMap<i32, Option<Vec<u32>>> {
+        let mut fingerprints = HashMap::new();
+        let mut missing = Vec::new();
+        for track in tracks.filter(|track| track.cue_track == 0) {
+            let key = acoustic_fingerprint_key(track);
+            match (&track.acoustic_fingerprint, &track.acoustic_fingerprint_key) {
+                (Some(fingerprint), Some(stored_key)) if key.as_ref() == Some(stored_key) => {
+                    fingerprints.insert(track.id, Some(decode_fingerprint(fingerprint)));
+                }
+                _ => missing.push((track.id, key, stored_path(&track.path, track.path_bytes.as_deref()))),
+            }
+        }
+        if !missing.is_empty() {
+            println!("Fingerprinting {} tracks...", missing.len());
+        }
+        let computed: Vec<(i32, Option<String>, Option<Vec<u32>>)> = stream::iter(missing)
+            .map(|(track_id, key, path)| {
+                let fpcalc_path = self.options.fpcalc_path.clone();
+                async move {
+                    if stats.is_cancelled() {
+                        return (track_id, None, None)
+                    }
+                    let fingerprint = tokio::task::spawn_blocking(move || read_acoustic_fingerprint(&fpcalc_path, &path))
+                        .await
+                        .unwrap_or_default();
+                    (track_id, key, fingerprint)
+                }
+            })
+            .buffer_unordered(self.options.workers.max(1))
+            .collect()
+            .await;
+        for (track_id, key, fingerprint) in computed {
+            if let (Some(key), Some(fingerprint)) = (key, &fingerprint) {
+                self.track_service.store_acoustic_fingerprint(track_id, key, encode_fingerprint(fingerprint)).await.unwrap();
+            }
+            fingerprints.insert(track_id, fingerprint);
+        }
+        fingerprints
+    }
+}
+
+/// Splits the candidates into groups of tracks that sound the same, a track joins a group when it matches every track in it.
+/// Tracks split from a CUE sheet or that fpcalc cannot read are matched by their tags: they join the fingerprinted
+/// tracks when those all sound the same, otherwise they form a group of their own.
+fn confirm_acoustically(tracks: Vec<track::Model>, fingerprints: &HashMap<i32, Option<Vec<u32>>>) -> Vec<Vec<track::Model>> {
+    let mut groups: Vec<Vec<(track::Model, &Vec<u32>)>> = Vec::new();
+    let mut unfingerprinted = Vec::new();
+    for track in tracks {
+        let Some(fingerprint) = fingerprints.get(&track.id).and_then(Option::as_ref) else {
+            println!("Unable to fingerprint track: {}, matching it by its tags", track.path);
+            unfingerprinted.push(track);
+            continue;
+        };
+        let group = groups.iter_mut().find(|group| {
+            group.iter().all(|(_, other)| acoustic_similarity(fingerprint, other) >= ACOUSTIC_MATCH)
+        });
+        match group {
+            Some(group) => group.push((track, fingerprint)),
+            None => groups.push(vec![(track, fingerprint)]),
+        }
+    }
+
+    let mut groups: Vec<Vec<track::Model>> = groups.into_iter()
+        .map(|group| group.into_iter().map(|(track, _)| track).collect())
+        .collect();
+    match groups.as_mut_slice() {
+        [group] => group.extend(unfingerprinted),
+        _ if !unfingerprinted.is_empty() => groups.push(unfingerprinted),
+        _ => {}
+    }
+    groups
+}
+
+/// Identifies the version of the file a fingerprint was computed from by its size and modification time
+fn acoustic_fingerprint_key(track: &track::Model) -> Option<String> {
+    Some(format!("{}:{}", track.file_size?, track.modified_at?))
+}
+
+fn encode_fingerprint(fingerprint: &[u32]) -> Vec<u8> {
+    fingerprint.iter().flat_map(|item| item.to_le_bytes()).collect()
+}
+
+fn decode_fingerprint(bytes: &[u8]) -> Vec<u32> {
+    bytes.chunks_exact(4)
+        .map(|item| u32::from_le_bytes([item[0], item[1], item[2], item[3]]))
+        .collect()
+}
+
+/// Lowercases the title and drops remaster and featuring notes, punctuation and extra whitespace
+fn normalize_title(title: &str) -> String {
+    let title = TITLE_NOTE.replace_all(title, "");
+    let title = TITLE_SUFFIX.replace(&title, "");
+    title.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits tracks sorted by duration wherever the gap to the previous track exceeds the tolerance in milliseconds
+fn split_by_duration(tracks: Vec<track::Model>, tolerance: i64) -> Vec<Vec<track::Model>> {
+    let mut clusters: Vec<Vec<track::Model>> = Vec::new();
+    for track in tracks {
+        match clusters.last_mut() {
+            Some(cluster) if cluster.last().is_some_and(|last| track.duration.unwrap_or(0) - last.duration.unwrap_or(0) <= tolerance) => cluster.push(track),
+            _ => clusters.push(vec![track]),
+        }
+    }
+    clusters
+}
+
+/// Ranks the copies of a song: lossless first, then higher bit depth and sample rate,
+/// then higher bitrate for lossy copies, then album copies over compilation copies, then the oldest track
+fn preference(track: &track::Model, compilation: bool) -> (bool, i32, i32, i32, bool, Reverse<i32>) {
+    let lossless = track.codec.as_deref().is_some_and(|codec| LOSSLESS_CODECS.contains(&codec));
+    let (bit_depth, sample_rate, bitrate) = match lossless {
+        true => (track.bit_depth.unwrap_or(0), track.sample_rate.unwrap_or(0), 0),
+        false => (0, 0, track.bitrate.unwrap_or(0)),
+    };
+    (lossless, bit_depth, sample_rate, bitrate, !compilation, Reverse(track.id))
+}
+
+/// Runs `fpcalc -raw` on the start of the file and parses the raw Chromaprint fingerprint
+fn read_acoustic_fingerprint(fpcalc_path: &str, path: &Path) -> Option<Vec<u32>> {
+    let output = Command::new(fpcalc_path)
+        .arg("-raw")
+        .arg("-length")
+        .arg(ACOUSTIC_LENGTH.to_string())
+        .arg(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None
+    }
+    String::from_utf8_lossy(&output.stdout).lines()
+        .find_map(|line| line.strip_prefix("FINGERPRINT="))
+        .map(|fingerprint| fingerprint.split(',')
+            .filter_map(|item| item.trim().parse::<i64>().ok())
+            .map(|item| item as u32)
+            .collect())
+        .filter(|fingerprint: &Vec<u32>| !fingerprint.is_empty())
+}
+
+/// Share of equal bits between the fingerprints, at the shift where they line up best
+fn acoustic_similarity(fingerprint: &[u32], other: &[u32]) -> f64 {
+    (-ACOUSTIC_MAX_SHIFT..=ACOUSTIC_MAX_SHIFT)
+        .filter_map(|shift| {
+            let pairs: Vec<(u32, u32)> = fingerprint.iter()
+                .enumerate()
+                .filter_map(|(index, item)| Some((*item, *other.get(usize::try_from(index as isize + shift).ok()?)?)))
+                .collect();
+            if pairs.len() < 16 {
+                return None
+            }
+            let differing: u32 = pairs.iter().map(|(item, other)| (item ^ other).count_ones()).sum();
+            Some(1.0 - differing as f64 / (pairs.len() * 32) as f64)
+        })
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(id: i32, duration: i64) -> track::Model {
+        track::Model {
+            id,
+            title: format!("Track {}", id),
+            path: format!("/music/{}.flac", id),
+            track_number: 1,
+            duration: Some(duration),
+            album_id: 1,
+            disc_number: 1,
+            disc_total: None,
+            file_size: None,
+            modified_at: None,
+            content_hash: None,
+            codec: None,
+            container: None,
+            bitrate: None,
+            sample_rate: None,
+            bit_depth: None,
+            channels: None,
+            track_gain: None,
+            track_peak: None,
+            cue_track: 0,
+            start_offset: None,
+            end_offset: None,
+            duplicate_of: None,
+            path_bytes: None,
+            acoustic_fingerprint: None,
+            acoustic_fingerprint_key: None,
+        }
+    }
+
+    /// A fingerprint of pseudo-random items, fingerprints of different seeds share about half of their bits
+    fn fingerprint(seed: u32) -> Vec<u32> {
+        let mut state = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+        (0..64).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        }).collect()
+    }
+
+    fn ids(groups: &[Vec<track::Model>]) -> Vec<Vec<i32>> {
+        groups.iter().map(|group| group.iter().map(|track| track.id).collect()).collect()
+    }
+
+    #[test]
+    fn normalizes_titles() {
+        assert_eq!(normalize_title("Battery (Remastered)"), "battery");
+        assert_eq!(normalize_title("Battery - 2016 Remaster"), "battery");
+        assert_eq!(normalize_title("One [feat. Someone]"), "one");
+        assert_eq!(normalize_title("  Don't   Stop Me Now! "), "don t stop me now");
+        assert_ne!(normalize_title("Battery (Live)"), normalize_title("Battery"));
+    }
+
+    #[test]
+    fn splits_by_duration() {
+        let tracks = vec![track(1, 100_000), track(2, 101_000), track(3, 102_500), track(4, 110_000)];
+        assert_eq!(ids(&split_by_duration(tracks, 1_500)), vec![vec![1, 2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn prefers_lossless_then_quality_then_album_copies() {
+        let mut flac = track(1, 0);
+        flac.codec = Some("flac".to_string());
+        flac.bit_depth = Some(16);
+        flac.sample_rate = Some(44_100);
+        let mut hires = flac.clone();
+        hires.id = 2;
+        hires.bit_depth = Some(24);
+        let mut mp3 = track(3, 0);
+        mp3.codec = Some("mp3".to_string());
+        mp3.bitrate = Some(320);
+        assert!(preference(&hires, false) > preference(&flac, false));
+        assert!(preference(&flac, true) > preference(&mp3, false));
+        assert!(preference(&flac, false) > preference(&flac, true));
+        assert!(preference(&track(4, 0), false) > preference(&track(5, 0), false));
+    }
+
+    #[test]
+    fn compares_fingerprints() {
+        let original = fingerprint(1);
+        assert_eq!(acoustic_similarity(&original, &original), 1.0);
+        let shifted: Vec<u32> = original[3..].to_vec();
+        assert_eq!(acoustic_similarity(&original, &shifted), 1.0);
+        assert!(acoustic_similarity(&original, &fingerprint(2)) < ACOUSTIC_MATCH);
+        assert_eq!(acoustic_similarity(&original, &original[..8]), 0.0);
+    }
+
+    #[test]
+    fn groups_tracks_that_sound_the_same() {
+        let fingerprints = HashMap::from([
+            (1, Some(fingerprint(1))),
+            (2, Some(fingerprint(2))),
+            (3, Some(fingerprint(1))),
+        ]);
+        let groups = confirm_acoustically(vec![track(1, 0), track(2, 0), track(3, 0)], &fingerprints);
+        assert_eq!(ids(&groups), vec![vec![1, 3], vec![2]]);
+    }
+
+    #[test]
+    fn unfingerprinted_tracks_do_not_join_different_recordings() {
+        let fingerprints = HashMap::from([(1, Some(fingerprint(1))), (2, None), (3, Some(fingerprint(2)))]);
+        let groups = confirm_acoustically(vec![track(1, 0), track(2, 0), track(3, 0)], &fingerprints);
+        assert_eq!(ids(&groups), vec![vec![1], vec![3], vec![2]]);
+    }
+
+    #[test]
+    fn unfingerprinted_tracks_join_a_single_recording() {
+        let fingerprints = HashMap::from([(1, None), (2, Some(fingerprint(1))), (3, Some(fingerprint(1)))]);
+        let groups = confirm_acoustically(vec![track(1, 0), track(2, 0), track(3, 0)], &fingerprints);
+        assert_eq!(ids(&groups), vec![vec![2, 3, 1]]);
+
+        let groups = confirm_acoustically(vec![track(1, 0), track(2, 0)], &HashMap::new());
+        assert_eq!(ids(&groups), vec![vec![1, 2]]);
+    }
+}
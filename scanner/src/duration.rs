@@ -0,0 +1,136 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use service::track::AudioProperties;
+
+const ASF_HEADER_GUID: [u8; 16] = [0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11, 0xA6, 0xD9, 0x00, 0xAA, 0x00, 0x62, 0xCE, 0x6C];
+const ASF_FILE_PROPERTIES_GUID: [u8; 16] = [0xA1, 0xDC, 0xAB, 0x8C, 0x47, 0xA9, 0xCF, 0x11, 0x8E, 0xE4, 0x00, 0xC0, 0x0C, 0x20, 0x53, 0x65];
+
+/// Frame sizes in bytes (including the frame header) for each AMR-NB frame type
+const AMR_NB_FRAME_SIZES: [u64; 16] = [13, 14, 16, 18, 20, 21, 27, 32, 6, 1, 1, 1, 1, 1, 1, 1];
+/// Frame sizes in bytes (including the frame header) for each AMR-WB frame type
+const AMR_WB_FRAME_SIZES: [u64; 16] = [18, 24, 33, 37, 41, 47, 51, 59, 61, 6, 1, 1, 1, 1, 1, 1];
+const AMR_FRAME_DURATION: i64 = 20;
+
+/// Reads the duration in milliseconds from the headers of containers that lofty cannot parse (WMA, AMR and DSF)
+pub(crate) fn read_container_duration(path: &Path) -> Option<i64> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut magic = [0u8; 16];
+    reader.read_exact(&mut magic).ok()?;
+    reader.seek(SeekFrom::Start(0)).ok()?;
+
+    if magic == ASF_HEADER_GUID {
+        read_asf_duration(&mut reader)
+    } else if magic.starts_with(b"#!AMR-WB\n") {
+        read_amr_duration(&mut reader, 9, &AMR_WB_FRAME_SIZES)
+    } else if magic.starts_with(b"#!AMR\n") {
+        read_amr_duration(&mut reader, 6, &AMR_NB_FRAME_SIZES)
+    } else if magic.starts_with(b"DSD ") {
+        read_dsf_duration(&mut reader)
+    } else {
+        None
+    }
+}
+
+/// Reads the audio properties from the headers of containers that lofty cannot parse (WMA, AMR and DSF)
+pub(crate) fn read_container_properties(path: &Path) -> Option<AudioProperties> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut magic = [0u8; 16];
+    reader.read_exact(&mut magic).ok()?;
+
+    let (container, codec) = if magic == ASF_HEADER_GUID {
+        ("asf", "wma")
+    } else if magic.starts_with(b"#!AMR-WB\n") {
+        ("amr", "amr-wb")
+    } else if magic.starts_with(b"#!AMR\n") {
+        ("amr", "amr")
+    } else if magic.starts_with(b"DSD ") {
+        return read_dsf_properties(&mut reader)
+    } else {
+        return None
+    };
+    Some(AudioProperties {
+        codec: Some(codec.to_string()),
+        container: Some(container.to_string()),
+        ..Default::default()
+    })
+}
+
+/// The DSF format chunk holds the channel count and sampling frequency, DSD always stores 1 bit per sample
+fn read_dsf_properties<R: Read + Seek>(reader: &mut R) -> Option<AudioProperties> {
+    reader.seek(SeekFrom::Start(28 + 24)).ok()?;
+    let channels = read_u32_le(reader)?;
+    let sampling_frequency = read_u32_le(reader)?;
+    Some(AudioProperties {
+        codec: Some("dsd".to_string()),
+        container: Some("dsf".to_string()),
+        bitrate: i32::try_from(sampling_frequency as u64 * channels as u64 / 1000).ok(),
+        sample_rate: i32::try_from(sampling_frequency).ok(),
+        bit_depth: Some(1),
+        channels: i32::try_from(channels).ok(),
+    })
+}
+
+/// The ASF file properties object stores the play duration in 100ns units, including the preroll in milliseconds
+fn read_asf_duration<R: Read + Seek>(reader: &mut R) -> Option<i64> {
+    reader.seek(SeekFrom::Start(24)).ok()?;
+    let header_objects = read_u32_le(reader)?;
+    let mut position = 30;
+    for _ in 0..header_objects {
+        reader.seek(SeekFrom::Start(position)).ok()?;
+        let mut guid = [0u8; 16];
+        reader.read_exact(&mut guid).ok()?;
+        let size = read_u64_le(reader)?;
+        if guid == ASF_FILE_PROPERTIES_GUID {
+            reader.seek(SeekFrom::Start(position.checked_add(64)?)).ok()?;
+            let play_duration = read_u64_le(reader)?;
+            reader.seek(SeekFrom::Current(8)).ok()?;
+            let preroll = read_u64_le(reader)?;
+            return i64::try_from((play_duration / 10_000).saturating_sub(preroll)).ok()
+        }
+        if size < 24 {
+            return None
+        }
+        // Object sizes come from the file, a crafted size must not wrap the position around
+        position = position.checked_add(size)?;
+    }
+    None
+}
+
+/// AMR frames always hold 20ms of audio, so the duration is the number of frames times 20ms
+fn read_amr_duration<R: Read + Seek>(reader: &mut R, magic_length: u64, frame_sizes: &[u64; 16]) -> Option<i64> {
+    let mut position = reader.seek(SeekFrom::Start(magic_length)).ok()?;
+    let mut frames = 0;
+    let mut header = [0u8; 1];
+    while reader.read_exact(&mut header).is_ok() {
+        let frame_type = (header[0] >> 3) & 0x0F;
+        position += frame_sizes[frame_type as usize];
+        reader.seek(SeekFrom::Start(position)).ok()?;
+        frames += 1;
+    }
+    Some(frames * AMR_FRAME_DURATION)
+}
+
+/// The DSF format chunk directly follows the 28 byte DSD chunk and holds the sampling frequency and sample count
+fn read_dsf_duration<R: Read + Seek>(reader: &mut R) -> Option<i64> {
+    reader.seek(SeekFrom::Start(28 + 28)).ok()?;
+    let sampling_frequency = read_u32_le(reader)? as u64;
+    reader.seek(SeekFrom::Current(4)).ok()?;
+    let sample_count = read_u64_le(reader)?;
+    if sampling_frequency == 0 {
+        return None
+    }
+    i64::try_from(sample_count.checked_mul(1000)? / sampling_frequency).ok()
+}
+
+fn read_u32_le<R: Read>(reader: &mut R) -> Option<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer).ok()?;
+    Some(u32::from_le_bytes(buffer))
+}
+
+fn read_u64_le<R: Read>(reader: &mut R) -> Option<u64> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer).ok()?;
+    Some(u64::from_le_bytes(buffer))
+}
@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use entities::track::Model;
+
+/// Identifies the contents of a track file, so changed files can be re-read
+pub(crate) struct Fingerprint {
+    pub size: i64,
+    /// Last modification time as a unix timestamp in seconds
+    pub modified_at: i64,
+    /// blake3 hash of the file contents, only computed when content hashing is enabled
+    pub content_hash: Option<String>,
+}
+
+impl Fingerprint {
+    pub fn read(path: &Path, hash_contents: bool) -> io::Result<Self> {
+        let metadata = path.metadata()?;
+        let modified_at = metadata.modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+        let content_hash = if hash_contents {
+            Some(hash_file(path)?)
+        } else {
+            None
+        };
+
+        Ok(Fingerprint {
+            size: metadata.len() as i64,
+            modified_at,
+            content_hash,
+        })
+    }
+
+    /// Checks whether the stored track still matches the file on disk.
+    /// The content hash is only compared when content hashing is enabled,
+    /// tracks stored without a hash are treated as changed so their hash gets stored.
+    pub fn matches(&self, track: &Model) -> bool {
+        if track.file_size != Some(self.size) || track.modified_at != Some(self.modified_at) {
+            return false
+        }
+        match (&self.content_hash, &track.content_hash) {
+            (Some(hash), Some(stored_hash)) => hash == stored_hash,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+pub(crate) fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
@@ -0,0 +1,100 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::path::Path;
+use lofty::config::ParseOptions;
+use lofty::file::AudioFile;
+use lofty::flac::FlacFile;
+use lofty::mp4::{AtomData, AtomIdent, Mp4File};
+use lofty::mpeg::MpegFile;
+use lofty::ogg::{OpusFile, VorbisFile};
+use lofty::ogg::tag::VorbisComments;
+use entities::sea_orm_active_enums::GenreKind;
+use service::genre::{genre_key, GenreCreate, TrackGenreCreate};
+use crate::{track_key, Scanner, TrackKey};
+
+/// Reads the styles of the track. lofty drops tags it has no key for, so the `STYLE` tag written by Discogs taggers
+/// is read from the Vorbis comments, ID3v2 `TXXX:STYLE` frame or iTunes freeform atom of the file.
+pub(crate) fn read_styles(path: &Path) -> Vec<String> {
+    let Ok(mut file) = File::open(path) else {
+        return vec![]
+    };
+    let options = ParseOptions::new().read_properties(false).read_cover_art(false);
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    let styles = match extension.as_str() {
+        "flac" => FlacFile::read_from(&mut file, options).ok()
+            .and_then(|flac| flac.vorbis_comments().map(vorbis_styles)),
+        "ogg" => VorbisFile::read_from(&mut file, options).ok()
+            .map(|vorbis| vorbis_styles(vorbis.vorbis_comments())),
+        "opus" => OpusFile::read_from(&mut file, options).ok()
+            .map(|opus| vorbis_styles(opus.vorbis_comments())),
+        "mp3" => MpegFile::read_from(&mut file, options).ok()
+            .and_then(|mpeg| mpeg.id3v2().and_then(|id3v2| id3v2.get_user_text("STYLE")).map(|style| vec![style.to_string()])),
+        "m4a" | "alac" => Mp4File::read_from(&mut file, options).ok()
+            .and_then(|mp4| mp4.ilst().and_then(|ilst| ilst.get(&AtomIdent::Freeform { mean: "com.apple.iTunes".into(), name: "STYLE".into() })).map(|atom| {
+                atom.data()
+                    .filter_map(|data| match data {
+                        AtomData::UTF8(style) => Some(style.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            })),
+        _ => None,
+    };
+    styles.unwrap_or_default()
+}
+
+fn vorbis_styles(comments: &VorbisComments) -> Vec<String> {
+    comments.get_all("STYLE").map(|style| style.to_string()).collect()
+}
+
+/// Collects the genres, moods and styles of a track as they are tagged
+pub(crate) fn track_genres(genres: Vec<String>, moods: Vec<String>, styles: Vec<String>) -> Vec<GenreCreate> {
+    let genres = genres.into_iter().map(|name| GenreCreate { name, kind: GenreKind::Genre });
+    let moods = moods.into_iter().map(|name| GenreCreate { name, kind: GenreKind::Mood });
+    let styles = styles.into_iter().map(|name| GenreCreate { name, kind: GenreKind::Style });
+    genres.chain(moods).chain(styles).collect()
+}
+
+/// Splits values like `Rock; Pop` on the separators and drops duplicates that only differ in spelling
+fn split_genres(genres: Vec<GenreCreate>, separators: &[char]) -> Vec<GenreCreate> {
+    let mut seen = HashSet::new();
+    genres.into_iter()
+        .flat_map(|genre| {
+            let kind = genre.kind;
+            genre.name.split(|c| separators.contains(&c) || c == '\0')
+                .map(|name| name.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|name| !genre_key(name).is_empty())
+                .map(move |name| GenreCreate { name, kind })
+                .collect::<Vec<_>>()
+        })
+        .filter(|genre| seen.insert((genre.kind, genre_key(&genre.name))))
+        .collect()
+}
+
+impl Scanner {
+    /// Replaces the genres of the scanned tracks, then links the album to the genres of all its tracks
+    pub(crate) async fn store_genres(&self, album: &entities::album::Model, track_genres: Vec<(TrackKey, Vec<GenreCreate>)>) {
+        let track_ids: HashMap<TrackKey, i32> = self.track_service.get_all_by_album(album.clone()).await.unwrap()
+            .into_iter()
+            .map(|track| (track_key(&track), track.id))
+            .collect();
+        let track_genres: Vec<(i32, Vec<GenreCreate>)> = track_genres.into_iter()
+            .filter_map(|(key, genres)| Some((*track_ids.get(&key)?, split_genres(genres, &self.options.genre_separators))))
+            .collect();
+        let genres = split_genres(track_genres.iter().flat_map(|(_, genres)| genres.clone()).collect(), &[]);
+        let genre_ids: HashMap<(GenreKind, String), i32> = self.genre_service.get_or_create_many(genres).await.unwrap()
+            .into_iter()
+            .map(|genre| ((genre.kind, genre.key), genre.id))
+            .collect();
+
+        let track_ids = track_genres.iter().map(|(track_id, _)| *track_id).collect();
+        let links = track_genres.iter()
+            .flat_map(|(track_id, genres)| genres.iter().filter_map(|genre| {
+                let genre_id = *genre_ids.get(&(genre.kind, genre_key(&genre.name)))?;
+                Some(TrackGenreCreate { track_id: *track_id, genre_id })
+            }))
+            .collect();
+        self.genre_service.replace_for_tracks(track_ids, links).await.unwrap();
+        self.genre_service.refresh_album(album.id).await.unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use walkdir::{DirEntry, WalkDir};
+use crate::is_hidden;
+
+/// Name of the gitignore-style files that exclude paths from the directory they are in and below
+pub(crate) const IGNORE_FILE: &str = ".bragiignore";
+
+/// Decides which paths in the library the scanner skips: hidden files, the exclude globs from the configuration,
+/// and the patterns in `.bragiignore` files at any directory level.
+/// Patterns in deeper ignore files take precedence, so a `!` pattern can include a path again.
+/// When symlinks are followed, paths that lead to a directory or file already reachable by another path are skipped as well.
+pub(crate) struct IgnoreRules {
+    root: PathBuf,
+    excludes: GlobSet,
+    /// Parsed ignore file of every directory looked at since the last reload, None for directories without one
+    ignore_files: Mutex<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
+    follow_symlinks: bool,
+    /// Preferred path of every directory and symlinked file in the library, keyed by its canonical path
+    aliases: Mutex<HashMap<PathBuf, PathBuf>>,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path, exclude_globs: &[Glob], follow_symlinks: bool) -> Self {
+        let mut excludes = GlobSetBuilder::new();
+        for glob in exclude_globs {
+            excludes.add(glob.clone());
+        }
+        IgnoreRules {
+            root: root.to_path_buf(),
+            excludes: excludes.build().unwrap(),
+            ignore_files: Mutex::new(HashMap::new()),
+            follow_symlinks,
+            aliases: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Walks the directory like the scanner does, following symlinks when enabled.
+    /// walkdir detects symlink loops and yields them as errors instead of entering them.
+    pub fn walk(&self, path: &Path) -> WalkDir {
+        WalkDir::new(path).follow_links(self.follow_symlinks)
+    }
+
+    /// Finds the directories and symlinked files that are reachable by several paths when symlinks are followed.
+    /// Paths without symlinks in them are preferred, then the first path in file name order,
+    /// so the same path is scanned every time.
+    pub fn index_aliases(&self) {
+        if !self.follow_symlinks {
+            return
+        }
+        let Ok(canonical_root) = self.root.canonicalize() else {
+            return
+        };
+        let mut aliases: HashMap<PathBuf, (PathBuf, bool)> = HashMap::new();
+        let mut entries = self.walk(&self.root).min_depth(1).sort_by_file_name().into_iter()
+            .filter_entry(|entry| {
+                (entry.file_type().is_dir() || entry.path_is_symlink())
+                    && !is_hidden(entry) && !self.matches(entry.path(), entry.file_type().is_dir())
+            });
+        while let Some(entry) = entries.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    if e.loop_ancestor().is_some() {
+                        println!("Skipping symlink loop: {}", e);
+                    }
+                    continue;
+                }
+            };
+            let Ok(canonical) = entry.path().canonicalize() else {
+                continue;
+            };
+            let direct = entry.path().strip_prefix(&self.root).is_ok_and(|relative| canonical_root.join(relative) == canonical);
+            match aliases.entry(canonical) {
+                Entry::Vacant(vacant) => {
+                    vacant.insert((entry.into_path(), direct));
+                }
+                Entry::Occupied(mut occupied) if direct && !occupied.get().1 => {
+                    occupied.insert((entry.into_path(), direct));
+                }
+                // The contents of an alias were seen under the preferred path already
+                Entry::Occupied(_) => {
+                    if entry.file_type().is_dir() {
+                        entries.skip_current_dir();
+                    }
+                }
+            }
+        }
+        *self.aliases.lock().unwrap() = aliases.into_iter()
+            .map(|(canonical, (path, _))| (canonical, path))
+            .collect();
+    }
+
+    /// Forgets the parsed ignore files, so changes to them are picked up
+    pub fn reload(&self) {
+        self.ignore_files.lock().unwrap().clear();
+    }
+
+    /// Checks whether a walked entry is skipped.
+    /// Only the entry itself is checked, the walk is expected to skip the entries of skipped directories.
+    pub fn skips(&self, entry: &DirEntry) -> bool {
+        let is_dir = entry.file_type().is_dir();
+        is_hidden(entry) || self.matches(entry.path(), is_dir)
+            || ((is_dir || entry.path_is_symlink()) && self.is_alias(entry.path(), is_dir))
+    }
+
+    /// Checks whether the path or any of its parent directories in the library is skipped.
+    /// Paths outside the library are never skipped.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false
+        };
+        let mut current = self.root.clone();
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            current.push(component);
+            let is_dir = components.peek().is_some() || current.is_dir();
+            let hidden = component.as_os_str().as_encoded_bytes().starts_with(b".");
+            if hidden || self.matches(&current, is_dir) || ((is_dir || current.is_symlink()) && self.is_alias(&current, is_dir)) {
+                return true
+            }
+        }
+        false
+    }
+
+    /// Checks whether the directory or symlinked file is reachable by another, preferred path.
+    /// A symlinked file is an alias when the directory of its target is scanned.
+    fn is_alias(&self, path: &Path, is_dir: bool) -> bool {
+        if !self.follow_symlinks {
+            return false
+        }
+        let Ok(canonical) = path.canonicalize() else {
+            return false
+        };
+        let aliases = self.aliases.lock().unwrap();
+        if !is_dir && canonical.parent().is_some_and(|dir| aliases.contains_key(dir)) {
+            return true
+        }
+        aliases.get(&canonical).is_some_and(|preferred| preferred != path)
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false
+        };
+        // Globs like `**/Scans/**` match the contents of the directory, they skip the directory itself as well
+        if self.excludes.is_match(relative) || (is_dir && self.excludes.is_match(relative.join("_"))) {
+            return true
+        }
+        let mut ignore_files = self.ignore_files.lock().unwrap();
+        for dir in path.ancestors().skip(1).take_while(|dir| dir.starts_with(&self.root)) {
+            let ignore_file = ignore_files.entry(dir.to_path_buf())
+                .or_insert_with(|| read_ignore_file(dir));
+            match ignore_file.as_ref().map(|ignore_file| ignore_file.matched(path, is_dir)) {
+                Some(Match::Ignore(_)) => return true,
+                Some(Match::Whitelist(_)) => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+/// Parses the ignore file of the directory, lines that are not valid patterns are reported and skipped
+fn read_ignore_file(dir: &Path) -> Option<Arc<Gitignore>> {
+    let path = dir.join(IGNORE_FILE);
+    if !path.is_file() {
+        return None
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&path) {
+        println!("Invalid patterns in {}: {}", path.display(), e);
+    }
+    builder.build().ok().map(Arc::new)
+}
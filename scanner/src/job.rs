@@ -0,0 +1,136 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use sea_orm::DbErr;
+use entities::sea_orm_active_enums::ScanState;
+use crate::{ScanStats, Scanner};
+
+/// How often the progress of a running scan job is stored
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum ScanError {
+    /// Another scan job is still running, holds its ID
+    AlreadyRunning(i32),
+    Database(DbErr),
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::AlreadyRunning(id) => write!(f, "Scan job {} is already running", id),
+            ScanError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<DbErr> for ScanError {
+    fn from(e: DbErr) -> Self {
+        ScanError::Database(e)
+    }
+}
+
+impl Scanner {
+    /// Starts a scan job in the background and returns its ID
+    pub async fn start_scan(self: Arc<Self>) -> Result<i32, ScanError> {
+        let (job_id, stats) = self.acquire_scan().await?;
+        tokio::spawn(self.run_scan(job_id, stats));
+        Ok(job_id)
+    }
+
+    /// Runs a scan job and waits until it finished
+    pub async fn scan(self: Arc<Self>) -> Result<i32, ScanError> {
+        let (job_id, stats) = self.acquire_scan().await?;
+        self.run_scan(job_id, stats).await;
+        Ok(job_id)
+    }
+
+    /// Asks the running scan job to stop after the track it is currently reading.
+    /// Returns false when the scan job is not running.
+    pub async fn cancel_scan(&self, job_id: i32) -> bool {
+        match self.running_scan.lock().await.as_ref() {
+            Some((running_id, stats)) if *running_id == job_id => {
+                println!("Cancelling scan job {}", job_id);
+                stats.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Creates a scan job, unless another one is still running
+    async fn acquire_scan(&self) -> Result<(i32, Arc<ScanStats>), ScanError> {
+        let mut running_scan = self.running_scan.lock().await;
+        if let Some((running_id, _)) = running_scan.as_ref() {
+            return Err(ScanError::AlreadyRunning(*running_id))
+        }
+        let scan_job = self.scan_job_service.create().await?;
+        let stats = Arc::new(ScanStats::default());
+        *running_scan = Some((scan_job.id, stats.clone()));
+        Ok((scan_job.id, stats))
+    }
+
+    /// Stores what a batch of watcher events changed as a finished scan job, so its issues show up in the scan history.
+    /// Batches that changed nothing and found no issues are not stored.
+    pub(crate) async fn store_watcher_batch(&self, stats: &ScanStats) {
+        if stats.is_empty() {
+            return
+        }
+        let job_id = match self.scan_job_service.create().await {
+            Ok(scan_job) => scan_job.id,
+            Err(e) => {
+                println!("Unable to store the changes of the watcher: {}", e);
+                return
+            }
+        };
+        self.store_issues(job_id, stats).await;
+        if let Err(e) = self.scan_job_service.finish(job_id, ScanState::Completed, stats.progress()).await {
+            println!("Unable to finish scan job {}: {}", job_id, e);
+        }
+    }
+
+    async fn store_issues(&self, job_id: i32, stats: &ScanStats) {
+        if let Err(e) = self.scan_job_service.add_issues(job_id, stats.take_issues()).await {
+            println!("Unable to store issues of scan job {}: {}", job_id, e);
+        }
+    }
+
+    /// Scans the library while periodically storing the progress on the scan job.
+    /// The scan runs in its own task, so a panic marks the job as failed instead of leaving it running.
+    /// The scan waits for the watcher to finish the batch of events it is applying.
+    async fn run_scan(self: Arc<Self>, job_id: i32, stats: Arc<ScanStats>) {
+        let scanner = self.clone();
+        let scan_stats = stats.clone();
+        let mut scan = tokio::spawn(async move {
+            let _library = scanner.library_lock.lock().await;
+            scanner.scan_library(&scan_stats).await
+        });
+        let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut scan => break result,
+                _ = interval.tick() => {
+                    if let Err(e) = self.scan_job_service.update_progress(job_id, stats.progress()).await {
+                        println!("Unable to store progress of scan job {}: {}", job_id, e);
+                    }
+                    self.store_issues(job_id, &stats).await;
+                }
+            }
+        };
+
+        let state = match result {
+            Ok(()) if stats.is_cancelled() => ScanState::Cancelled,
+            Ok(()) => ScanState::Completed,
+            Err(e) => {
+                stats.error(format!("Scan failed: {}", e));
+                ScanState::Failed
+            }
+        };
+        self.store_issues(job_id, &stats).await;
+        if let Err(e) = self.scan_job_service.finish(job_id, state, stats.progress()).await {
+            println!("Unable to finish scan job {}: {}", job_id, e);
+        }
+        *self.running_scan.lock().await = None;
+    }
+}
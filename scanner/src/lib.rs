@@ -1,237 +1,912 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use walkdir::{DirEntry, WalkDir};
+use futures::stream::{self, StreamExt};
+use tokio::sync::{Mutex, Semaphore};
+use walkdir::DirEntry;
 use service::artist::{ArtistService, ArtistAlter, ArtistCreate};
-use service::album::{AlbumCreate, AlbumService};
-use regex::Regex;
-use service::track::{TrackCreate, TrackService};
+use service::album::{AlbumAlter, AlbumCreate, AlbumService};
+use service::track::{AudioProperties, TrackAlter, TrackCreate, TrackService};
+use service::genre::{GenreCreate, GenreService};
+use service::lyrics::LyricsService;
+use service::path::stored_path;
+use service::playlist::PlaylistService;
+use service::scan_job::ScanJobService;
+use service::track_artist::{TrackArtistCreate, TrackArtistService};
+use entities::sea_orm_active_enums::{ArtistRole, IssueReason, LyricsSource};
+use crate::credits::{is_various_artists, TrackCredits, VARIOUS_ARTISTS};
+use crate::cue::CueSheets;
+use crate::duration::{read_container_duration, read_container_properties};
+use crate::fingerprint::Fingerprint;
+use crate::genres::{read_styles, track_genres};
+use crate::ignore_rules::IgnoreRules;
+use crate::lyrics::{read_embedded_lyrics, read_sidecar_lyrics, TrackLyrics};
+use crate::metadata::{read_metadata, TrackMetadata};
+use crate::moves::MissingTracks;
+use crate::template::{AlbumMatch, TrackMatch};
 use once_cell::sync::Lazy;
+use regex::Regex;
+
+mod artwork;
+mod credits;
+mod cue;
+mod dry_run;
+mod duplicates;
+mod duration;
+mod fingerprint;
+mod genres;
+mod ignore_rules;
+mod job;
+mod lyrics;
+mod loudness;
+mod metadata;
+mod mount;
+mod moves;
+mod playlist;
+mod prune;
+mod stats;
+mod template;
+mod watcher;
+
+pub use crate::artwork::{read_embedded_picture, EmbeddedPicture};
+pub use globset::Glob;
+pub use crate::job::ScanError;
+pub use crate::lyrics::{parse_lrc, plain_lyrics, LyricsLine};
+pub use crate::prune::PruneReport;
+pub use crate::stats::ScanStats;
+pub use crate::template::{NamingTemplate, TemplateError};
 
 struct TrackInfo {
     title: String,
     track_number: i32,
+    disc_number: i32,
+    disc_total: Option<i32>,
+    /// Duration in milliseconds, None when it could not be read
+    duration: Option<i64>,
+    credits: TrackCredits,
+    properties: AudioProperties,
+    track_gain: Option<f64>,
+    track_peak: Option<f64>,
+    lyrics: Option<TrackLyrics>,
+    /// Genres, moods and styles as they are tagged, values are split when they are stored
+    genres: Vec<GenreCreate>,
+    /// Number of the track in the CUE sheet it is split from, 0 for tracks that are a file of their own
+    cue_track: i32,
+    start_offset: Option<i64>,
+    end_offset: Option<i64>,
+}
+
+struct AlbumInfo {
+    title: String,
+    release_year: i32,
+    album_artist: Option<String>,
+    compilation: bool,
+    album_gain: Option<f64>,
+    album_peak: Option<f64>,
+}
+
+/// Options controlling how the library is scanned
+pub struct ScannerOptions {
+    /// Hash the contents of every track, so edits that keep the size and modification time are noticed
+    pub hash_track_contents: bool,
+    /// Maximum number of artist and album directories scanned at the same time
+    pub workers: usize,
+    /// Layouts used to read album and track details from directory and file names when tags are missing,
+    /// tried in order
+    pub templates: Vec<NamingTemplate>,
+    /// Measure the loudness of tracks without ReplayGain tags after every scan
+    pub analyze_loudness: bool,
+    /// ffmpeg binary used for the loudness analysis
+    pub ffmpeg_path: String,
+    /// Directory searched for playlist files, the library itself when not set
+    pub playlist_path: Option<String>,
+    /// Characters separating several genres in a single tag value, like `Rock; Pop`
+    pub genre_separators: Vec<char>,
+    /// Milliseconds the durations of copies of the same song may differ by
+    pub duplicate_tolerance: i64,
+    /// Confirm copies of the same song by their Chromaprint fingerprints
+    pub acoustic_fingerprints: bool,
+    /// fpcalc binary used for the Chromaprint fingerprints
+    pub fpcalc_path: String,
+    /// Paths in the library the scanner skips, like `**/@eaDir/**`, matched relative to the library
+    pub exclude_globs: Vec<Glob>,
+    /// Follow symlinked directories and files, like artist folders linked in from other disks
+    pub follow_symlinks: bool,
+    /// Only scan the library while its path is a mount point, so an unmounted disk leaves the database untouched
+    pub require_mount_point: bool,
+}
+
+impl Default for ScannerOptions {
+    fn default() -> Self {
+        ScannerOptions {
+            hash_track_contents: false,
+            workers: 4,
+            templates: NamingTemplate::defaults(),
+            analyze_loudness: false,
+            ffmpeg_path: "ffmpeg".to_string(),
+            playlist_path: None,
+            genre_separators: vec![';', '/'],
+            duplicate_tolerance: 2000,
+            acoustic_fingerprints: false,
+            fpcalc_path: "fpcalc".to_string(),
+            exclude_globs: vec![],
+            follow_symlinks: false,
+            require_mount_point: false,
+        }
+    }
 }
 
 pub struct Scanner {
     artist_service: Arc<ArtistService>,
     album_service: Arc<AlbumService>,
     track_service: Arc<TrackService>,
+    track_artist_service: Arc<TrackArtistService>,
+    genre_service: Arc<GenreService>,
+    lyrics_service: Arc<LyricsService>,
+    playlist_service: Arc<PlaylistService>,
+    scan_job_service: Arc<ScanJobService>,
     library_path: String,
+    options: ScannerOptions,
+    /// Bounds the number of album directories scanned at the same time across all artists
+    album_permits: Semaphore,
+    /// Scan job currently running, only one library scan runs at a time
+    running_scan: Mutex<Option<(i32, Arc<ScanStats>)>>,
+    /// Held while a scan job or a batch of watcher events changes the library, so they never run at the same time
+    library_lock: Mutex<()>,
+    ignore_rules: IgnoreRules,
 }
 
 impl Scanner {
 
-    pub fn new(artist_service: Arc<ArtistService>, album_service: Arc<AlbumService>, track_service: Arc<TrackService>, library_path: String) -> Self {
-        Scanner { artist_service, album_service, track_service, library_path }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(artist_service: Arc<ArtistService>, album_service: Arc<AlbumService>, track_service: Arc<TrackService>, track_artist_service: Arc<TrackArtistService>, genre_service: Arc<GenreService>, lyrics_service: Arc<LyricsService>, playlist_service: Arc<PlaylistService>, scan_job_service: Arc<ScanJobService>, library_path: String, options: ScannerOptions) -> Self {
+        let album_permits = Semaphore::new(options.workers.max(1));
+        let ignore_rules = IgnoreRules::new(Path::new(&library_path), &options.exclude_globs, options.follow_symlinks);
+        Scanner { artist_service, album_service, track_service, track_artist_service, genre_service, lyrics_service, playlist_service, scan_job_service, library_path, options, album_permits, running_scan: Mutex::new(None), library_lock: Mutex::new(()), ignore_rules }
     }
-    pub async fn scan_library(&self) {
+
+    /// Scans every artist directory in the library, up to `ScannerOptions::workers` at the same time.
+    /// A cancelled scan stops before the next artist, album or track and skips pruning and the playlist import.
+    /// Nothing is scanned while the library is unavailable, like when its disk is not mounted.
+    pub async fn scan_library(&self, stats: &ScanStats) {
         println!("Scanning {}", self.library_path);
         println!("-------------------");
+        if let Some(reason) = self.library_unavailable().await {
+            stats.error(format!("{}, skipping the scan", reason));
+            return
+        }
+        self.ignore_rules.reload();
+        self.ignore_rules.index_aliases();
         let all_artists = self.artist_service.get_all().await.unwrap();
-        let artist_map: HashMap<String, (i32, Option<String>)> = all_artists
+        let artist_map: HashMap<String, entities::artist::Model> = all_artists
             .into_iter()
-            .map(|artist| (artist.name.clone(), (artist.id.clone(), artist.checksum.clone())))
+            .map(|artist| (artist.name.clone(), artist))
             .collect();
-        for entry in WalkDir::new(self.library_path.clone()).min_depth(1).max_depth(1).into_iter().filter_map(|e| e.ok()) {
-            if is_hidden(&entry) {
-                continue;
+        let artist_entries = list_directories(Path::new(&self.library_path), &self.ignore_rules, stats);
+        let missing = self.index_missing_tracks().await;
+        ScanStats::add(&stats.artists_total, artist_entries.len());
+        stream::iter(artist_entries)
+            .for_each_concurrent(self.options.workers.max(1), |entry| {
+                let artist_map = &artist_map;
+                let missing = &missing;
+                async move {
+                    if stats.is_cancelled() {
+                        return
+                    }
+                    self.scan_artist_directory(&entry, artist_map, missing, stats).await;
+                    ScanStats::add(&stats.artists_scanned, 1);
+                }
+            })
+            .await;
+        if stats.is_cancelled() {
+            println!("Scan cancelled");
+            return
+        }
+        let report = self.prune_library().await;
+        ScanStats::add(&stats.artists_removed, report.artists.len());
+        ScanStats::add(&stats.albums_removed, report.albums.len());
+        ScanStats::add(&stats.tracks_removed, report.tracks.len());
+        if !report.is_empty() {
+            println!("Removed {} artists, {} albums and {} tracks that no longer exist on disk",
+                     report.artists.len(), report.albums.len(), report.tracks.len());
+            for track in &report.tracks {
+                println!("Removed track: {}", track);
             }
-            if entry.file_type().is_dir() {
-                let artist_name = entry.file_name().to_str().unwrap();
-                println!("Found Artist directory: {}", entry.path().display());
-                let current_hash = hash_artist_folder(entry.path().to_str().unwrap());
-                if let Some((artist_id, stored_hash)) = artist_map.get(&artist_name.to_string()) {
-                    println!("Artist already exists in the database: {}", artist_name);
-                    if stored_hash.is_none() {
-                        println!("Artist checksum not found, updating...");
-                        self.artist_service.alter(artist_id.clone(), ArtistAlter {
-                            checksum: Some(current_hash.clone()),
-                            name: None,
-                            path: None
-                        }).await.expect("TODO: panic message");
-                        Box::pin(self.scan_artist(entry.path(), artist_id.clone())).await;
-                        continue
+        }
+        self.import_playlists(stats).await;
+        self.backfill_durations().await;
+        self.backfill_properties().await;
+        self.detect_duplicates(stats).await;
+        self.refresh_artwork(None).await;
+        if self.options.analyze_loudness {
+            self.analyze_loudness(stats).await;
+        }
+        println!("-------------------");
+    }
 
+    /// Scans the artist directory when its checksum changed since the last scan.
+    /// The checksum is only stored once the artist was scanned completely,
+    /// so a cancelled scan picks the artist up again next time.
+    /// A new directory holding the files of an artist whose directory disappeared renames that artist.
+    async fn scan_artist_directory(&self, entry: &DirEntry, artist_map: &HashMap<String, entities::artist::Model>, missing: &MissingTracks, stats: &ScanStats) {
+        let artist_name = entry.file_name().to_string_lossy();
+        println!("Found Artist directory: {}", entry.path().display());
+        let current_hash = hash_artist_folder(entry.path(), &self.ignore_rules, self.options.hash_track_contents);
+        if let Some(artist) = artist_map.get(artist_name.as_ref()) {
+            println!("Artist already exists in the database: {}", artist_name);
+            self.store_artist_path(artist, entry.path()).await;
+            let Some(stored_hash) = &artist.checksum else {
+                println!("Artist checksum not found, updating...");
+                Box::pin(self.scan_artist(entry.path(), artist.id, missing, stats)).await;
+                self.store_artist_checksum(artist.id, current_hash, stats).await;
+                return
+
+            };
+            if *stored_hash != current_hash {
+                println!("Artist checksum does not match, updating...");
+                ScanStats::add(&stats.artists_updated, 1);
+                Box::pin(self.scan_artist(entry.path(), artist.id, missing, stats)).await;
+                self.store_artist_checksum(artist.id, current_hash, stats).await;
+            } else {
+                println!("Artist checksum matches, no update needed.");
+            }
+        } else if let Some(artist_id) = self.adopt_moved_artist(&artist_name, entry.path(), missing).await {
+            ScanStats::add(&stats.artists_updated, 1);
+            Box::pin(self.scan_artist(entry.path(), artist_id, missing, stats)).await;
+            self.store_artist_checksum(artist_id, current_hash, stats).await;
+        } else {
+            println!("Artist does not exist in the database: {}", artist_name);
+
+            let artist = ArtistCreate {
+                name: artist_name.to_string(),
+                path: Some(entry.path().to_path_buf()),
+                checksum: None,
+            };
+
+            let artist = self.artist_service.get_or_create(artist).await.unwrap();
+            println!("Created new artist in the database: {}", artist.name);
+            ScanStats::add(&stats.artists_added, 1);
+            Box::pin(self.scan_artist(entry.path(), artist.id, missing, stats)).await;
+            self.store_artist_checksum(artist.id, current_hash, stats).await;
+        }
+    }
+
+    /// Stores the artist directory as the path of the artist,
+    /// artists first found in the tags of another artist's album have no path until their own directory is scanned
+    async fn store_artist_path(&self, artist: &entities::artist::Model, path: &Path) {
+        let stored = artist.path.as_deref().map(|stored| stored_path(stored, artist.path_bytes.as_deref()));
+        if stored.as_deref() == Some(path) {
+            return
+        }
+        self.artist_service.alter(artist.id, ArtistAlter {
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        }).await.unwrap();
+        println!("Updated artist path in the database: {}", path.display());
+    }
+
+    async fn store_artist_checksum(&self, artist_id: i32, checksum: String, stats: &ScanStats) {
+        if stats.is_cancelled() {
+            return
+        }
+        let altered = self.artist_service.alter(artist_id, ArtistAlter {
+            checksum: Some(checksum.clone()),
+            ..Default::default()
+        }).await;
+        match altered {
+            Ok(_) => println!("Updated artist checksum in the database: {}", checksum),
+            // The artist is scanned again by the next scan
+            Err(e) => stats.error(format!("Unable to store the checksum of artist {}: {}", artist_id, e)),
+        }
+    }
+
+    /// Reads the duration of tracks stored by older scans, which stored no real duration
+    async fn backfill_durations(&self) {
+        let tracks = self.track_service.get_without_duration().await.unwrap();
+        if tracks.is_empty() {
+            return
+        }
+        println!("Backfilling the duration of {} tracks...", tracks.len());
+        for track in tracks {
+            // Tracks whose duration still cannot be read stay unknown and are retried by the next scan
+            let Some(duration) = read_duration(&stored_path(&track.path, track.path_bytes.as_deref())) else {
+                continue;
+            };
+            self.track_service.alter(track.id, TrackAlter {
+                duration: Some(Some(duration)),
+                ..Default::default()
+            }).await.unwrap();
+        }
+    }
+
+    /// Reads the audio properties of tracks stored by older scans
+    async fn backfill_properties(&self) {
+        let tracks = self.track_service.get_without_codec().await.unwrap();
+        if tracks.is_empty() {
+            return
+        }
+        println!("Backfilling the audio properties of {} tracks...", tracks.len());
+        for track in tracks {
+            let Some(properties) = read_properties(&stored_path(&track.path, track.path_bytes.as_deref())) else {
+                continue;
+            };
+            self.track_service.alter(track.id, TrackAlter {
+                properties: Some(properties),
+                ..Default::default()
+            }).await.unwrap();
+        }
+    }
+
+    /// Scans the artist directory for albums, sharing the album permits with every other artist
+    async fn scan_artist(&self, path: &Path, artist_id: i32, missing: &MissingTracks, stats: &ScanStats) {
+        let albums_map = self.albums_map().await;
+        let album_entries = list_directories(path, &self.ignore_rules, stats);
+        stream::iter(album_entries)
+            .for_each_concurrent(None, |entry| {
+                let albums_map = &albums_map;
+                async move {
+                    let _permit = self.album_permits.acquire().await.unwrap();
+                    if stats.is_cancelled() {
+                        return
                     }
-                    if stored_hash.clone().unwrap() != current_hash {
-                        println!("Artist checksum does not match, updating...");
-                        self.artist_service.alter(artist_id.clone(), ArtistAlter {
-                            checksum: Some(current_hash.clone()),
-                            name: None,
-                            path: None
-                        }).await.expect("TODO: panic message");
-                        println!("Updated artist checksum in the database: {}", current_hash);
-                        Box::pin(self.scan_artist(entry.path(), artist_id.clone())).await;
-                    } else {
-                        println!("Artist checksum matches, no update needed.");
-                        continue;
-                    }
-                } else {
-                    println!("Artist does not exist in the database: {}", artist_name);
-
-                    let artist = ArtistCreate {
-                        name: artist_name.to_string(),
-                        path: self.library_path.to_string(),
-                        checksum: Some(current_hash.clone()),
-                    };
-
-                    let artist = self.artist_service.create(artist).await.unwrap();
-                    println!("Created new artist in the database: {}", artist.name);
-                    Box::pin(self.scan_artist(entry.path(), artist.id)).await;
+                    self.scan_album_directory(path, entry.path(), artist_id, albums_map, missing, stats).await;
                 }
+            })
+            .await;
+    }
+
+    /// Maps the artist id and title of every stored album to its id
+    async fn albums_map(&self) -> HashMap<(i32, String), i32> {
+        self.album_service.get_all().await.unwrap()
+            .into_iter()
+            .map(|album| ((album.artist_id, album.title.clone()), album.id))
+            .collect()
+    }
+
+    /// Adds the album directory to the database if needed, then scans it for tracks.
+    /// Album title, year and album artist are read from the tags of the album's tracks,
+    /// falling back to the `Album (Year)` folder naming convention when the tags are missing.
+    /// Compilations without an album artist tag are stored under Various Artists.
+    /// Albums are found by artist and title, or by the files of an album whose directory disappeared,
+    /// so renamed and moved albums keep their id.
+    async fn scan_album_directory(&self, artist_path: &Path, path: &Path, artist_id: i32, albums_map: &HashMap<(i32, String), i32>, missing: &MissingTracks, stats: &ScanStats) {
+        let artist_name = artist_path.file_name().map(|s| s.to_string_lossy()).unwrap_or_default();
+        let Some(AlbumInfo { title: album_name, release_year, album_artist, compilation, album_gain, album_peak }) = read_album_info(path, &self.options.templates, &self.ignore_rules, stats) else {
+            return;
+        };
+        let album_artist = album_artist.or_else(|| compilation.then(|| VARIOUS_ARTISTS.to_string()));
+        let album_artist_id = match album_artist {
+            Some(album_artist) if !album_artist.eq_ignore_ascii_case(&artist_name) => {
+                self.get_or_create_artist(&album_artist, None, stats).await
+            }
+            _ => artist_id,
+        };
+        println!("Found album: {}, which came out in {}", album_name, release_year);
+        let stored_album = match albums_map.get(&(album_artist_id, album_name.clone())) {
+            Some(album_id) => self.album_service.get_by_id(*album_id).await.unwrap(),
+            None => self.find_moved_album(path, missing).await,
+        };
+        if let Some(mut album) = stored_album {
+            println!("Album already exists in database, scanning for new tracks...");
+            // The directory was renamed or moved, or the title changed in the tags.
+            // Albums stored by older scans hold the artist directory as their path, which is replaced as well.
+            if stored_path(&album.path, album.path_bytes.as_deref()) != path || album.title != album_name {
+                println!("Album renamed or moved from {} to {}", album.path, path.display());
+                let altered = self.album_service.alter(album.id, AlbumAlter {
+                    title: Some(album_name.clone()),
+                    path: Some(path.to_path_buf()),
+                    release_year: Some(release_year),
+                    artist_id: Some(album_artist_id),
+                    ..Default::default()
+                }).await;
+                album = match altered {
+                    Ok(album) => album,
+                    // Another album of the artist already has the title, so the tracks of the moved album join it.
+                    // The moved album is left without tracks and pruned.
+                    Err(e) => match self.album_service.get_by_artist_and_title(album_artist_id, &album_name).await.unwrap() {
+                        Some(existing) => {
+                            println!("Merging album {} into the stored album with the same title", album.path);
+                            existing
+                        }
+                        None => {
+                            stats.error(format!("Unable to update album: {}, {}", path.display(), e));
+                            return;
+                        }
+                    },
+                };
+            }
+            if Box::pin(self.scan_album(path, album.id, missing, stats)).await {
+                self.album_service.alter(album.id, AlbumAlter {
+                    compilation: Some(compilation),
+                    album_gain: Some(album_gain),
+                    album_peak: Some(album_peak),
+                    ..Default::default()
+                }).await.unwrap();
+                ScanStats::add(&stats.albums_updated, 1);
             }
+        } else {
+            println!("Album does not exist in the database, adding it and scanning for new tracks...");
+            let album = AlbumCreate {
+                title: album_name,
+                path: path.to_path_buf(),
+                release_year,
+                artist_id: album_artist_id,
+                compilation,
+                album_gain,
+                album_peak,
+            };
+            let album = self.album_service.get_or_create(album).await.unwrap();
+            println!("Added album: {}, to the database", album.title);
+            ScanStats::add(&stats.albums_added, 1);
+            Box::pin(self.scan_album(path, album.id, missing, stats)).await;
         }
-        println!("-------------------");
     }
 
-    /// Scans the artist directory for albums
-    async fn scan_artist(&self, path: &Path, artist_id: i32) {
-        let artist_albums = self.album_service.get_all().await.unwrap();
-        let albums_map: HashMap<String, i32> = artist_albums
+    /// Finds an artist by name, creating it when it does not exist yet.
+    /// Artists only known from tags are created without a path.
+    async fn get_or_create_artist(&self, name: &str, path: Option<&Path>, stats: &ScanStats) -> i32 {
+        if let Some(artist) = self.artist_service.get_by_exact_name(name).await.unwrap() {
+            return artist.id
+        }
+        let artist = ArtistCreate {
+            name: name.to_string(),
+            path: path.map(Path::to_path_buf),
+            checksum: None,
+        };
+        let artist = self.artist_service.get_or_create(artist).await.unwrap();
+        println!("Created new artist in the database: {}", artist.name);
+        ScanStats::add(&stats.artists_added, 1);
+        artist.id
+    }
+
+    /// Scans the album folder and its disc folders for tracks.
+    /// Stored tracks are only re-read when their size, modification time or content hash changed.
+    /// Files split by a CUE sheet are stored as a track per CUE track, replacing the tracks previously split from the file.
+    /// Returns whether any track was added, updated or removed.
+    async fn scan_album(&self, path: &Path, album_id: i32, missing: &MissingTracks, stats: &ScanStats) -> bool {
+        let album = self.album_service.get_by_id(album_id).await.unwrap().unwrap();
+        let album_tracks = self.track_service.get_all_by_album(album.clone()).await.unwrap();
+        let stored_tracks: HashMap<TrackKey, entities::track::Model> = album_tracks
             .into_iter()
-            .map(|album| (album.title.clone(), album.id.clone()))
+            .map(|track| (track_key(&track), track))
             .collect();
-        for entry in WalkDir::new(path).min_depth(1).max_depth(1).into_iter().filter_map(|e| e.ok()) {
-            if is_hidden(&entry) {
-                continue;
+        let track_ids = stored_tracks.values().map(|track| track.id).collect();
+        let sidecar_lyrics: HashSet<i32> = self.lyrics_service.get_by_track_ids(track_ids).await.unwrap()
+            .into_iter()
+            .filter(|lyrics| lyrics.source == LyricsSource::Sidecar)
+            .map(|lyrics| lyrics.track_id)
+            .collect();
+        let cue_sheets = CueSheets::read(path, &self.ignore_rules);
+        let disc_folders = count_disc_folders(path, &self.ignore_rules);
+        let mut new_tracks = Vec::new();
+        let mut updated_tracks = 0;
+        let mut track_credits = Vec::new();
+        let mut track_lyrics = Vec::new();
+        let mut track_genres = Vec::new();
+        let mut scanned_tracks: HashMap<PathBuf, HashSet<i32>> = HashMap::new();
+        for entry in list_tracks(path, &self.ignore_rules, stats) {
+            if stats.is_cancelled() {
+                break
             }
-            if entry.file_type().is_dir() {
-                let release_year = extract_release_year(&entry);
-                let album_name = extract_album_name(&entry);
-                if release_year.is_none() || album_name.is_none() {
-                    println!("Unable to parse album: {}", entry.path().display());
-                    println!("Check the readme.md for the naming convention");
+            println!("Found Track: {}", entry.path().display());
+            let track_path = entry.path().to_path_buf();
+            let fingerprint = match Fingerprint::read(entry.path(), self.options.hash_track_contents) {
+                Ok(fingerprint) => fingerprint,
+                Err(e) => {
+                    stats.error(format!("Unable to read track: {}, {}", entry.path().display(), e));
+                    continue;
+                }
+            };
+            let tracks = if cue_sheets.splits(entry.path()) {
+                if cue_sheets.unchanged(entry.path(), &stored_tracks, &fingerprint) {
+                    println!("Tracks split from the CUE sheet already exist in the database");
+                    continue;
+                }
+                cue_sheets.track_info(&entry)
+            } else {
+                if let Some(stored_track) = stored_tracks.get(&(track_path.clone(), 0)).filter(|track| fingerprint.matches(track)) {
+                    println!("Track already exists in the database");
+                    // Sidecar lyrics can change without the track changing, the embedded lyrics take over a removed sidecar
+                    match read_sidecar_lyrics(entry.path()) {
+                        Some(lyrics) => track_lyrics.push(((track_path, 0), Some(lyrics))),
+                        None if sidecar_lyrics.contains(&stored_track.id) => {
+                            let lyrics_tag = read_metadata(entry.path()).ok().and_then(|metadata| metadata.lyrics);
+                            track_lyrics.push(((track_path, 0), read_embedded_lyrics(entry.path(), lyrics_tag.as_deref())));
+                        }
+                        None => {}
+                    }
                     continue;
                 }
-                println!("Found album: {}, which came out in {}", album_name.clone().unwrap(), release_year.clone().unwrap());
-                if let Some(album_id) = albums_map.get(&album_name.clone().unwrap()) {
-                    println!("Album already exists in database, scanning for new tracks...");
-                    Box::pin(self.scan_album(entry.path(), artist_id, album_id.clone())).await;
-                } else {
-                    println!("Album does not exist in the database, adding it and scanning for new tracks...");
-                    let album = AlbumCreate {
-                        title: album_name.clone().unwrap(),
-                        path: path.to_str().unwrap().to_string(),
-                        release_year: release_year.unwrap().parse().unwrap(),
-                        artist_id,
-                    };
-                    let album = self.album_service.create(album).await.unwrap();
-                    println!("Added album: {}, to the database", album.title);
-                    Box::pin(self.scan_album(entry.path(), artist_id, album.id)).await;
+                match extract_track_info(&entry, &self.options.templates) {
+                    Ok(track_data) => vec![track_data],
+                    Err(reason) => {
+                        stats.issue(entry.path(), reason);
+                        continue;
+                    }
                 }
+            };
+            // Claimed only once the track info was read, tracks of a file that cannot be read stay missing
+            let mut moved_tracks = match tracks.is_empty() || stored_tracks.keys().any(|(path, _)| *path == track_path) {
+                true => HashMap::new(),
+                false => missing.claim(entry.path(), &fingerprint),
+            };
+
+            for track_data in tracks {
+                let key = (track_path.clone(), track_data.cue_track);
+                scanned_tracks.entry(track_path.clone()).or_default().insert(track_data.cue_track);
+                let moved_track = moved_tracks.remove(&track_data.cue_track);
+                if let Some(stored_track) = stored_tracks.get(&key).or(moved_track.as_ref()) {
+                    println!("Track changed on disk, updating...");
+                    let track = self.track_service.alter(stored_track.id, TrackAlter {
+                        title: Some(track_data.title),
+                        path: Some(entry.path().to_path_buf()),
+                        album_id: Some(album_id),
+                        track_number: Some(track_data.track_number),
+                        duration: Some(track_data.duration),
+                        disc_number: Some(track_data.disc_number),
+                        disc_total: track_data.disc_total.or(disc_folders),
+                        file_size: Some(fingerprint.size),
+                        modified_at: Some(fingerprint.modified_at),
+                        content_hash: fingerprint.content_hash.clone(),
+                        properties: Some(track_data.properties),
+                        track_gain: Some(track_data.track_gain),
+                        track_peak: Some(track_data.track_peak),
+                        start_offset: Some(track_data.start_offset),
+                        end_offset: Some(track_data.end_offset),
+                    }).await.unwrap();
+                    println!("Updated track in database: {}", track.title);
+                    track_lyrics.push((key.clone(), track_data.lyrics));
+                    track_genres.push((key.clone(), track_data.genres));
+                    track_credits.push((key, track_data.credits));
+                    updated_tracks += 1;
+                    continue;
+                }
+
+                println!("Track does not exist in database");
+                track_credits.push((key.clone(), track_data.credits));
+                track_genres.push((key.clone(), track_data.genres));
+                track_lyrics.push((key, track_data.lyrics));
+                new_tracks.push(TrackCreate {
+                    title: track_data.title,
+                    duration: track_data.duration,
+                    album_id,
+                    path: entry.path().to_path_buf(),
+                    track_number: track_data.track_number,
+                    disc_number: track_data.disc_number,
+                    disc_total: track_data.disc_total.or(disc_folders),
+                    file_size: fingerprint.size,
+                    modified_at: fingerprint.modified_at,
+                    content_hash: fingerprint.content_hash.clone(),
+                    properties: track_data.properties,
+                    track_gain: track_data.track_gain,
+                    track_peak: track_data.track_peak,
+                    cue_track: track_data.cue_track,
+                    start_offset: track_data.start_offset,
+                    end_offset: track_data.end_offset,
+                });
             }
         }
+        ScanStats::add(&stats.tracks_updated, updated_tracks);
+        // Tracks of rescanned files that are no longer split the same way, like when a CUE sheet was added or removed
+        let replaced_tracks: Vec<i32> = stored_tracks.iter()
+            .filter(|((path, cue_track), _)| scanned_tracks.get(path).is_some_and(|cue_tracks| !cue_tracks.contains(cue_track)))
+            .map(|(_, track)| track.id)
+            .collect();
+        if !replaced_tracks.is_empty() {
+            let removed = self.track_service.delete_many(replaced_tracks).await.unwrap();
+            ScanStats::add(&stats.tracks_removed, removed as usize);
+        }
+        let changed = !scanned_tracks.is_empty();
+        if !new_tracks.is_empty() {
+            let created = self.track_service.create_many(new_tracks).await.unwrap();
+            println!("Created {} new tracks in database", created);
+            ScanStats::add(&stats.tracks_added, created as usize);
+        }
+        self.store_lyrics(&album, track_lyrics).await;
+        if !changed {
+            return false
+        }
+        self.store_credits(&album, track_credits, stats).await;
+        self.store_genres(&album, track_genres).await;
+        true
     }
 
-    /// Scans the album folder for tracks
-    /// Check track durations if it exists in db, since users may change files with the same filename.
-    async fn scan_album(&self, path: &Path, artist_id: i32, album_id: i32) {
-        let album = self.album_service.get_by_id(album_id).await.unwrap().unwrap();
-        let album_tracks = self.track_service.get_all_by_album(album).await.unwrap();
-        let stored_tracks: Vec<String> = album_tracks.iter().map(|e| e.title.clone()).collect();
-        for entry in WalkDir::new(path).min_depth(1).max_depth(1).into_iter().filter_map(|e| e.ok()) {
-            if is_hidden(&entry) {
+    /// Replaces the artist credits of the scanned tracks.
+    /// Tracks without a track artist are credited to the album artist, unless the album is a compilation.
+    /// Artists only known from the tags are created without a path.
+    async fn store_credits(&self, album: &entities::album::Model, track_credits: Vec<(TrackKey, TrackCredits)>, stats: &ScanStats) {
+        let track_ids: HashMap<TrackKey, i32> = self.track_service.get_all_by_album(album.clone()).await.unwrap()
+            .into_iter()
+            .map(|track| (track_key(&track), track.id))
+            .collect();
+        let mut artist_ids: HashMap<String, i32> = HashMap::new();
+        let mut credited_tracks = Vec::new();
+        let mut credits = Vec::new();
+        for (key, track_credits) in track_credits {
+            let Some(track_id) = track_ids.get(&key) else {
                 continue;
+            };
+            credited_tracks.push(*track_id);
+            if !track_credits.has_primary() && !album.compilation {
+                credits.push(TrackArtistCreate { track_id: *track_id, artist_id: album.artist_id, role: ArtistRole::Primary });
             }
-            if entry.file_type().is_file() && is_audio_file(&entry.path()) {
-                let track_data = extract_track_info(&entry).unwrap();
-                println!("Found Track: {}", entry.path().display());
-                if stored_tracks.contains(&track_data.title.to_string()) {
-                    println!("Track already exists in the database");
-                } else {
-                    println!("Track does not exist in database");
-                    let track = TrackCreate {
-                        title: track_data.title,
-                        duration: 1337,
-                        album_id,
-                        path: entry.path().to_str().unwrap().to_string(),
-                        track_number: track_data.track_number,
-                    };
-
-                    let track = self.track_service.create(track).await.unwrap();
-                    println!("Created new track in database: {}", track.title);
+            for (name, role) in track_credits.credits {
+                let artist_id = match artist_ids.get(&name) {
+                    Some(artist_id) => *artist_id,
+                    None => {
+                        let artist_id = self.get_or_create_artist(&name, None, stats).await;
+                        artist_ids.insert(name, artist_id);
+                        artist_id
+                    }
+                };
+                if !credits.iter().any(|c: &TrackArtistCreate| c.track_id == *track_id && c.artist_id == artist_id && c.role == role) {
+                    credits.push(TrackArtistCreate { track_id: *track_id, artist_id, role });
                 }
             }
         }
+        self.track_artist_service.replace_for_tracks(credited_tracks, credits).await.unwrap();
     }
 }
 
 fn is_hidden(entry: &DirEntry) -> bool {
-    entry.file_name()
-        .to_str()
-        .map(|s| s.starts_with("."))
-        .unwrap_or(false)
+    entry.file_name().as_encoded_bytes().starts_with(b".")
 }
 
-fn extract_release_year(entry: &DirEntry) -> Option<String> {
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\((\d{4})\)").unwrap());
-    let Some(result) = RE.captures(entry.file_name().to_str().unwrap()) else {
-        return None
-    };
+/// Lists the directories in the library or artist directory.
+/// Files are skipped, loose audio files are reported.
+fn list_directories(path: &Path, rules: &IgnoreRules, stats: &ScanStats) -> Vec<DirEntry> {
+    rules.walk(path).min_depth(1).max_depth(1).sort_by_file_name().into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| !rules.skips(entry))
+        .filter(|entry| {
+            if entry.file_type().is_dir() {
+                return true
+            }
+            if is_audio_file(entry.path()) {
+                stats.issue(entry.path(), IssueReason::UnexpectedLocation);
+            }
+            false
+        })
+        .collect()
+}
 
-    result.get(1).map(|x| x.as_str().to_string())
+/// Lists the audio files in the album directory, including the files in `Disc 1` or `CD2` style disc folders.
+/// Companion files like covers, cue sheets and rip logs are skipped silently,
+/// other files and nested directories are reported.
+fn list_tracks(path: &Path, rules: &IgnoreRules, stats: &ScanStats) -> Vec<DirEntry> {
+    collect_tracks(path, true, rules, stats)
 }
 
-fn extract_album_name(entry: &DirEntry) -> Option<String> {
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.*)\s\(\d{4}\)").unwrap());
-    let Some(result) = RE.captures(entry.file_name().to_str().unwrap()) else {
-        return None
-    };
+fn collect_tracks(path: &Path, include_disc_folders: bool, rules: &IgnoreRules, stats: &ScanStats) -> Vec<DirEntry> {
+    let mut tracks = Vec::new();
+    for entry in rules.walk(path).min_depth(1).max_depth(1).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        if rules.skips(&entry) {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            if include_disc_folders && disc_folder_number(entry.path()).is_some() {
+                tracks.extend(collect_tracks(entry.path(), false, rules, stats));
+            } else {
+                stats.issue(entry.path(), IssueReason::UnexpectedLocation);
+            }
+            continue;
+        }
+        if is_audio_file(entry.path()) {
+            tracks.push(entry);
+        } else if !is_companion_file(entry.path()) {
+            stats.issue(entry.path(), IssueReason::UnsupportedExtension);
+        }
+    }
+    tracks
+}
 
-    result.get(1).map(|x| x.as_str().to_string())
+/// Reads the disc number from a `Disc 1`, `Disk 2` or `CD3` style folder name
+fn disc_folder_number(path: &Path) -> Option<i32> {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^(?:disc|disk|cd)\s*[-_.]?\s*(\d+)\b").unwrap());
+    let result = RE.captures(path.file_name()?.to_str()?)?;
+
+    result.get(1).and_then(|x| x.as_str().parse().ok())
 }
 
-// TODO: Refactor function to NOT return None if it fails to parse unneeded data, causing the program to panic
-fn extract_track_info(entry: &DirEntry) -> Option<TrackInfo> {
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?P<track_number>\d+)\s*[.-]\s*(?:(?P<artist>.+?)\s*-\s*)?(?P<title>.+)").unwrap());
+/// Counts the disc folders in the album directory, None when the album has no disc folders
+fn count_disc_folders(path: &Path, rules: &IgnoreRules) -> Option<i32> {
+    let discs = rules.walk(path).min_depth(1).max_depth(1).into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_dir() && disc_folder_number(entry.path()).is_some() && !rules.skips(entry))
+        .count();
+    Some(discs as i32).filter(|discs| *discs > 0)
+}
 
-    let file_stem = get_filename_stem(entry.path())?;
-    
-    let Some(result) = RE.captures(&file_stem) else {
-        println!("Unable to parse track info from track: {}", file_stem);
+/// Reads the album title, year and album artist from the tags of the album's tracks,
+/// falling back to the album's CUE sheet, then the naming templates for the album directory when the tags are missing.
+/// Albums without a title are skipped, albums without a year default to 0.
+fn read_album_info(path: &Path, templates: &[NamingTemplate], rules: &IgnoreRules, stats: &ScanStats) -> Option<AlbumInfo> {
+    let album_tags = read_album_tags(path, rules).unwrap_or_default();
+    let cue_sheets = CueSheets::read(path, rules);
+    let cue_sheet = cue_sheets.first();
+    let album_match = match_album_name(path, templates);
+    let Some(title) = album_tags.album
+        .or_else(|| cue_sheet.and_then(|sheet| sheet.title.clone()))
+        .or_else(|| album_match.as_ref().map(|album| album.title.clone())) else {
+        println!("Tag the album, or check the readme.md for the naming convention");
+        stats.issue(path, IssueReason::BadNaming);
         return None
     };
+    let release_year = album_tags.year
+        .or_else(|| cue_sheet.and_then(|sheet| sheet.year))
+        .or_else(|| album_match.and_then(|album| album.year))
+        .unwrap_or_else(|| {
+            stats.issue(path, IssueReason::MissingReleaseYear);
+            0
+        });
+    let compilation = album_tags.compilation || album_tags.album_artist.as_deref().is_some_and(is_various_artists);
+    Some(AlbumInfo {
+        title,
+        release_year,
+        album_artist: album_tags.album_artist,
+        compilation,
+        album_gain: album_tags.album_gain,
+        album_peak: album_tags.album_peak,
+    })
+}
 
-    let Some(track_number) = result.name("track_number").map(|x| x.as_str().to_string().parse::<i32>().unwrap()) else {
-        println!("Unable to parse track number, defaulting to null");
-        return None
+/// Reads the album tags from the first track in the album directory or its disc folders that carries an album tag
+fn read_album_tags(path: &Path, rules: &IgnoreRules) -> Option<TrackMetadata> {
+    rules.walk(path).min_depth(1).max_depth(2).sort_by_file_name().into_iter()
+        .filter_entry(|entry| !rules.skips(entry))
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.depth() == 1 || entry.path().parent().and_then(disc_folder_number).is_some())
+        .filter(|entry| entry.file_type().is_file() && is_audio_file(entry.path()))
+        .filter_map(|entry| read_metadata(entry.path()).ok())
+        .find(|metadata| metadata.album.is_some())
+}
+
+/// Matches the album directory name against the naming templates, the first matching template wins
+fn match_album_name(path: &Path, templates: &[NamingTemplate]) -> Option<AlbumMatch> {
+    let name = path.file_name()?.to_string_lossy();
+    templates.iter().find_map(|template| template.match_album(&name))
+}
+
+/// Extracts the track info from the embedded tags,
+/// falling back to the naming templates for the filename for missing tags.
+/// Fails with the reason the track has to be skipped when neither holds a title.
+fn extract_track_info(entry: &DirEntry, templates: &[NamingTemplate]) -> Result<TrackInfo, IssueReason> {
+    let (metadata, missing_title) = match read_metadata(entry.path()) {
+        Ok(metadata) => (metadata, IssueReason::BadNaming),
+        Err(e) => {
+            println!("Unable to read tags from track: {}, {}", entry.path().display(), e);
+            (TrackMetadata::default(), IssueReason::UnreadableTags)
+        }
     };
-    let Some(track_title) = result.name("title").map(|x| x.as_str().to_string()) else {
-        println!("Unable to parse track title");
-        return None
+    let filename_info = if metadata.title.is_none() || metadata.artist.is_none() || metadata.track_number.is_none() || metadata.disc_number.is_none() {
+        match_track_filename(entry, templates)
+    } else {
+        None
     };
 
-    Some(TrackInfo {
-        title: track_title.to_string(),
-        track_number
+    let title = metadata.title
+        .or_else(|| filename_info.as_ref().map(|track| track.title.clone()))
+        .ok_or(missing_title)?;
+    let track_number = metadata.track_number
+        .or_else(|| filename_info.as_ref().and_then(|track| track.track_number))
+        .unwrap_or_else(|| {
+            println!("Unable to find a track number for track: {}, defaulting to 0", title);
+            0
+        });
+    let disc_number = metadata.disc_number
+        .or_else(|| entry.path().parent().and_then(disc_folder_number))
+        .or_else(|| filename_info.as_ref().and_then(|track| track.disc_number))
+        .unwrap_or(1);
+    let artist = metadata.artist.or_else(|| filename_info.as_ref().and_then(|track| track.artist.clone()));
+    let credits = TrackCredits::new(artist.as_deref(), &title, metadata.composer.as_deref(), metadata.remixer.as_deref());
+    let properties = track_properties(entry.path(), metadata.properties);
+
+    Ok(TrackInfo {
+        title,
+        track_number,
+        disc_number,
+        disc_total: metadata.disc_total,
+        duration: metadata.duration.or_else(|| read_container_duration(entry.path())),
+        credits,
+        properties,
+        track_gain: metadata.track_gain,
+        track_peak: metadata.track_peak,
+        lyrics: read_sidecar_lyrics(entry.path())
+            .or_else(|| read_embedded_lyrics(entry.path(), metadata.lyrics.as_deref())),
+        genres: track_genres(metadata.genres, metadata.moods, read_styles(entry.path())),
+        cue_track: 0,
+        start_offset: None,
+        end_offset: None,
     })
 }
 
+/// Falls back to the container headers for the audio properties of files lofty cannot parse
+fn track_properties(path: &Path, properties: AudioProperties) -> AudioProperties {
+    match properties.codec {
+        Some(_) => properties,
+        None => read_container_properties(path).unwrap_or_default(),
+    }
+}
+
+/// Identifies a track within the album by the path of its file and its CUE track number,
+/// tracks split from a CUE sheet share the path of their file
+type TrackKey = (PathBuf, i32);
+
+fn track_key(track: &entities::track::Model) -> TrackKey {
+    (stored_path(&track.path, track.path_bytes.as_deref()), track.cue_track)
+}
+
+/// Matches the filename without its extension against the naming templates, the first matching template wins
+fn match_track_filename(entry: &DirEntry, templates: &[NamingTemplate]) -> Option<TrackMatch> {
+    let file_stem = get_filename_stem(entry.path())?;
+    let track = templates.iter().find_map(|template| template.match_track(&file_stem));
+    if track.is_none() {
+        println!("Unable to parse track info from track: {}", file_stem);
+    }
+    track
+}
+
+/// Reads the duration in milliseconds of an audio file, None when the container could not be parsed
+fn read_duration(path: &Path) -> Option<i64> {
+    read_metadata(path).ok()
+        .and_then(|metadata| metadata.duration)
+        .or_else(|| read_container_duration(path))
+}
+
+/// Reads the audio properties of an audio file, None when neither lofty nor the container headers know its codec
+fn read_properties(path: &Path) -> Option<AudioProperties> {
+    read_metadata(path).ok()
+        .map(|metadata| metadata.properties)
+        .filter(|properties| properties.codec.is_some())
+        .or_else(|| read_container_properties(path))
+}
+
 fn is_audio_file(path: &Path) -> bool {
     const AUDIO_EXTENSIONS: [&str; 13] = ["mp3", "flac", "wav", "ogg", "m4a", "aac", "alac", "aiff", "dsd", "opus", "wma", "amr", "ape", ];
     let file_ext: &str = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-    AUDIO_EXTENSIONS.iter().any(|&s| s == file_ext)
+    AUDIO_EXTENSIONS.contains(&file_ext)
 }
 
-fn hash_artist_folder(path: &str) -> String {
+/// Files that commonly sit next to the tracks of an album and are not reported when skipped
+fn is_companion_file(path: &Path) -> bool {
+    const COMPANION_EXTENSIONS: [&str; 16] = ["jpg", "jpeg", "png", "gif", "webp", "bmp", "cue", "log", "txt", "nfo", "m3u", "m3u8", "pls", "pdf", "accurip", "lrc"];
+    let file_ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    COMPANION_EXTENSIONS.contains(&file_ext.as_str())
+}
+
+/// Hashes the names, sizes and modification times of every file in the artist folder,
+/// and the contents of every track when `hash_track_contents` is set, so edits that keep the modification time are noticed.
+/// Used as a fast-path to skip artists whose files did not change since the last scan.
+/// Ignored files are left out, so changing the ignore rules changes the hash of the artists they apply to.
+fn hash_artist_folder(path: &Path, rules: &IgnoreRules, hash_track_contents: bool) -> String {
     let mut hasher = blake3::Hasher::new();
     
-    for entry in WalkDir::new(path).min_depth(1).into_iter().filter_map(|e| e.ok()) {
-        if is_hidden(&entry) {
-            continue;
-        }
+    for entry in rules.walk(path).min_depth(1).into_iter().filter_entry(|entry| !rules.skips(entry)).filter_map(|e| e.ok()) {
         if entry.file_type().is_file() {
-            let file_name = entry.file_name().to_str().unwrap();
-            hasher.update(file_name.as_bytes());
+            hasher.update(entry.file_name().as_encoded_bytes());
+            if let Ok(fingerprint) = Fingerprint::read(entry.path(), hash_track_contents && is_audio_file(entry.path())) {
+                hasher.update(&fingerprint.size.to_le_bytes());
+                hasher.update(&fingerprint.modified_at.to_le_bytes());
+                if let Some(content_hash) = fingerprint.content_hash {
+                    hasher.update(content_hash.as_bytes());
+                }
+            }
         }
     }
     
     hasher.finalize().to_hex().to_string()
 }
 
+/// CUE sheets and playlists are often written in a legacy encoding, anything that is not UTF-8 is read as Latin-1
+fn decode_text(content: Vec<u8>) -> String {
+    let content = match String::from_utf8(content) {
+        Ok(content) => content,
+        Err(e) => e.into_bytes().iter().map(|byte| *byte as char).collect(),
+    };
+    content.trim_start_matches('\u{feff}').to_string()
+}
+
 fn get_filename_stem(path: &Path) -> Option<String> {
     path.file_stem()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_string())
+        .map(|s| s.to_string_lossy().to_string())
 }
\ No newline at end of file
@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::process::Command;
+use futures::stream::{self, StreamExt};
+use service::album::AlbumAlter;
+use service::path::stored_path;
+use service::track::TrackAlter;
+use crate::{ScanStats, Scanner};
+
+/// Loudness in LUFS that ReplayGain 2.0 gains are relative to
+const REFERENCE_LOUDNESS: f64 = -18.0;
+
+/// Integrated loudness and true peak of a track, as measured by the EBU R128 filter of ffmpeg
+struct Loudness {
+    /// Integrated loudness in LUFS
+    integrated: f64,
+    /// True peak in dBFS
+    true_peak: f64,
+}
+
+impl Scanner {
+    /// Measures the loudness of every track without a ReplayGain track gain,
+    /// then computes the album gain of albums whose tracks all have a track gain.
+    /// Tracks ffmpeg cannot decode are reported as errors and measured again on the next scan.
+    pub(crate) async fn analyze_loudness(&self, stats: &ScanStats) {
+        let tracks = self.track_service.get_without_gain().await.unwrap();
+        if !tracks.is_empty() {
+            println!("Analyzing the loudness of {} tracks...", tracks.len());
+        }
+        stream::iter(tracks)
+            .for_each_concurrent(self.options.workers.max(1), |track| async move {
+                if stats.is_cancelled() {
+                    return
+                }
+                let ffmpeg_path = self.options.ffmpeg_path.clone();
+                let (path, start_offset, end_offset) = (stored_path(&track.path, track.path_bytes.as_deref()), track.start_offset, track.end_offset);
+                let loudness = tokio::task::spawn_blocking(move || measure_loudness(&ffmpeg_path, &path, start_offset, end_offset))
+                    .await
+                    .unwrap_or_else(|e| Err(e.to_string()));
+                match loudness {
+                    Ok(loudness) => {
+                        self.track_service.alter(track.id, TrackAlter {
+                            track_gain: Some(Some(REFERENCE_LOUDNESS - loudness.integrated)),
+                            track_peak: Some(Some(peak_to_linear(loudness.true_peak))),
+                            ..Default::default()
+                        }).await.unwrap();
+                    }
+                    Err(e) => stats.error(format!("Unable to analyze the loudness of track: {}, {}", track.path, e)),
+                }
+            })
+            .await;
+        if stats.is_cancelled() {
+            return
+        }
+        self.compute_album_gains().await;
+    }
+
+    /// Computes the album gain from the track gains, weighting the loudness of every track by its duration.
+    /// This approximates measuring the album as a whole, without decoding every track again.
+    async fn compute_album_gains(&self) {
+        for album in self.album_service.get_without_gain().await.unwrap() {
+            let tracks = self.track_service.get_by_album_id(album.id).await.unwrap().unwrap_or_default();
+            let Some(gains) = tracks.iter().map(|track| track.track_gain).collect::<Option<Vec<f64>>>() else {
+                continue;
+            };
+            if gains.is_empty() {
+                continue;
+            }
+            let (energy, duration) = tracks.iter().zip(&gains).fold((0.0, 0.0), |(energy, duration), (track, gain)| {
+                let weight = track.duration.unwrap_or(0).max(1) as f64;
+                let loudness = REFERENCE_LOUDNESS - gain;
+                (energy + weight * 10f64.powf(loudness / 10.0), duration + weight)
+            });
+            let album_loudness = 10.0 * (energy / duration).log10();
+            let album_peak = tracks.iter()
+                .map(|track| track.track_peak)
+                .collect::<Option<Vec<f64>>>()
+                .map(|peaks| peaks.into_iter().fold(0.0, f64::max));
+            self.album_service.alter(album.id, AlbumAlter {
+                album_gain: Some(Some(REFERENCE_LOUDNESS - album_loudness)),
+                album_peak: Some(album_peak),
+                ..Default::default()
+            }).await.unwrap();
+        }
+    }
+}
+
+/// Runs the file through the ffmpeg `ebur128` filter and reads the integrated loudness and true peak from its summary.
+/// Tracks split from a CUE sheet only measure their part of the file.
+fn measure_loudness(ffmpeg_path: &str, path: &Path, start_offset: Option<i64>, end_offset: Option<i64>) -> Result<Loudness, String> {
+    let mut command = Command::new(ffmpeg_path);
+    command.args(["-hide_banner", "-nostats"]);
+    if let Some(start_offset) = start_offset {
+        command.args(["-ss", &seconds(start_offset)]);
+    }
+    if let Some(end_offset) = end_offset {
+        command.args(["-to", &seconds(end_offset)]);
+    }
+    let output = command
+        .arg("-i")
+        .arg(path)
+        .args(["-vn", "-af", "ebur128=peak=true:framelog=verbose", "-f", "null", "-"])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("ffmpeg exited with {}", output.status))
+    }
+    let summary = String::from_utf8_lossy(&output.stderr);
+    let value = |label: &str| summary.lines()
+        .filter_map(|line| line.trim().strip_prefix(label))
+        .filter_map(|value| value.split_whitespace().next())
+        .filter_map(|value| value.parse::<f64>().ok())
+        .next_back();
+    let integrated = value("I:").filter(|loudness| loudness.is_finite()).ok_or("no integrated loudness in the ffmpeg output")?;
+    let true_peak = value("Peak:").ok_or("no true peak in the ffmpeg output")?;
+    Ok(Loudness { integrated, true_peak })
+}
+
+/// Formats milliseconds as the seconds ffmpeg expects for times
+fn seconds(milliseconds: i64) -> String {
+    format!("{}.{:03}", milliseconds / 1000, milliseconds % 1000)
+}
+
+fn peak_to_linear(decibels: f64) -> f64 {
+    10f64.powf(decibels / 20.0)
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::Path;
+use lofty::config::ParseOptions;
+use lofty::file::AudioFile;
+use lofty::id3::v2::{Frame, SynchronizedTextFrame, TimestampFormat};
+use lofty::mpeg::MpegFile;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use entities::sea_orm_active_enums::LyricsSource;
+use service::lyrics::LyricsCreate;
+use crate::{decode_text, track_key, Scanner, TrackKey};
+
+/// Timestamps like `[01:23.45]` in front of an LRC line, a line can have several
+static LRC_TIMESTAMP: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[(\d+):(\d{1,2})(?:[.:](\d{1,3}))?\]").unwrap());
+/// Shifts every timestamp by the milliseconds, positive values show the lines sooner
+static LRC_OFFSET: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^\[offset:\s*([+-]?\d+)\s*\]").unwrap());
+/// ID tags like `[ar:Artist]` at the top of an LRC file
+static LRC_ID_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[[a-zA-Z#]+:[^\]]*\]$").unwrap());
+
+/// Lyrics found for a track, synced lyrics are kept as LRC lines
+pub(crate) struct TrackLyrics {
+    pub content: String,
+    pub synced: bool,
+    pub source: LyricsSource,
+}
+
+/// A line of synced lyrics
+pub struct LyricsLine {
+    /// Time the line starts at in milliseconds
+    pub time: i64,
+    pub text: String,
+}
+
+impl TrackLyrics {
+    fn new(content: &str, source: LyricsSource) -> Option<Self> {
+        let content = content.trim_start_matches('\u{feff}').trim();
+        if content.is_empty() {
+            return None
+        }
+        Some(TrackLyrics { synced: !parse_lrc(content).is_empty(), content: content.to_string(), source })
+    }
+}
+
+impl Scanner {
+    /// Replaces the lyrics of the tracks in the album, `None` removes the stored lyrics of the track
+    pub(crate) async fn store_lyrics(&self, album: &entities::album::Model, track_lyrics: Vec<(TrackKey, Option<TrackLyrics>)>) {
+        if track_lyrics.is_empty() {
+            return
+        }
+        let track_ids: HashMap<TrackKey, i32> = self.track_service.get_all_by_album(album.clone()).await.unwrap()
+            .into_iter()
+            .map(|track| (track_key(&track), track.id))
+            .collect();
+        let mut replaced_tracks = Vec::new();
+        let mut lyrics = Vec::new();
+        for (key, track_lyrics) in track_lyrics {
+            let Some(track_id) = track_ids.get(&key) else {
+                continue;
+            };
+            replaced_tracks.push(*track_id);
+            if let Some(track_lyrics) = track_lyrics {
+                lyrics.push(LyricsCreate {
+                    track_id: *track_id,
+                    content: track_lyrics.content,
+                    synced: track_lyrics.synced,
+                    source: track_lyrics.source,
+                });
+            }
+        }
+        self.lyrics_service.replace_for_tracks(replaced_tracks, lyrics).await.unwrap();
+    }
+}
+
+/// Reads the `.lrc` file next to the track, named after the track. Files that are not UTF-8 are read as Latin-1.
+pub(crate) fn read_sidecar_lyrics(path: &Path) -> Option<TrackLyrics> {
+    ["lrc", "LRC"].iter()
+        .map(|extension| path.with_extension(extension))
+        .find(|sidecar| sidecar.is_file())
+        .and_then(|sidecar| fs::read(sidecar).ok())
+        .and_then(|content| TrackLyrics::new(&decode_text(content), LyricsSource::Sidecar))
+}
+
+/// Reads the lyrics embedded in the track, preferring an ID3v2 SYLT frame over the lyrics tag
+pub(crate) fn read_embedded_lyrics(path: &Path, lyrics_tag: Option<&str>) -> Option<TrackLyrics> {
+    read_synchronized_text(path)
+        .or_else(|| lyrics_tag.map(|lyrics| lyrics.to_string()))
+        .and_then(|content| TrackLyrics::new(&content, LyricsSource::Embedded))
+}
+
+/// Converts the SYLT frame of an MP3 file into LRC lines. lofty keeps SYLT frames as binary frames, so they are parsed here.
+/// Frames timed in MPEG frames instead of milliseconds are skipped.
+fn read_synchronized_text(path: &Path) -> Option<String> {
+    if !path.extension().and_then(|s| s.to_str()).is_some_and(|extension| extension.eq_ignore_ascii_case("mp3")) {
+        return None
+    }
+    let mut file = File::open(path).ok()?;
+    let mpeg_file = MpegFile::read_from(&mut file, ParseOptions::new()).ok()?;
+    let frame = mpeg_file.id3v2()?.into_iter().find_map(|frame| match frame {
+        Frame::Binary(binary) if binary.id().as_str() == "SYLT" => SynchronizedTextFrame::parse(&binary.data, binary.flags()).ok(),
+        _ => None,
+    })?;
+    if frame.timestamp_format != TimestampFormat::MS {
+        return None
+    }
+    let lines: Vec<String> = frame.content.iter()
+        .map(|(time, text)| format!("[{:02}:{:02}.{:02}]{}", time / 60_000, time / 1000 % 60, time % 1000 / 10, text.trim()))
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Parses LRC lines into synced lines ordered by time. Lines without a timestamp are skipped,
+/// so plain text lyrics parse into no lines at all.
+pub fn parse_lrc(content: &str) -> Vec<LyricsLine> {
+    let mut offset = 0;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        let mut rest = line.trim();
+        if let Some(captures) = LRC_OFFSET.captures(rest) {
+            offset = captures[1].parse().unwrap_or(0);
+            continue;
+        }
+        let mut times = Vec::new();
+        while let Some(captures) = LRC_TIMESTAMP.captures(rest) {
+            let minutes: i64 = captures[1].parse().unwrap_or(0);
+            let seconds: i64 = captures[2].parse().unwrap_or(0);
+            let fraction = captures.get(3).map_or(0, |fraction| {
+                let digits = fraction.as_str();
+                digits.parse::<i64>().unwrap_or(0) * 10_i64.pow(3 - digits.len() as u32)
+            });
+            times.push(minutes * 60_000 + seconds * 1000 + fraction);
+            rest = &rest[captures[0].len()..];
+        }
+        for time in times {
+            lines.push(LyricsLine { time, text: rest.trim().to_string() });
+        }
+    }
+    for line in &mut lines {
+        line.time = (line.time - offset).max(0);
+    }
+    lines.sort_by_key(|line| line.time);
+    lines
+}
+
+/// Returns the lyrics as plain text, stripping the timestamps and ID tags of LRC lyrics
+pub fn plain_lyrics(content: &str) -> String {
+    content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !LRC_ID_TAG.is_match(line))
+        .map(|line| {
+            let mut rest = line;
+            while let Some(timestamp) = LRC_TIMESTAMP.find(rest) {
+                rest = &rest[timestamp.end()..];
+            }
+            rest.trim()
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timestamps_in_order() {
+        let lines = parse_lrc("[ar:Artist]\n[00:12.50]Second\n[00:01.5]First\n[01:02.345]Third");
+        let lines: Vec<(i64, &str)> = lines.iter().map(|line| (line.time, line.text.as_str())).collect();
+        assert_eq!(lines, vec![(1_500, "First"), (12_500, "Second"), (62_345, "Third")]);
+    }
+
+    #[test]
+    fn repeats_lines_with_several_timestamps() {
+        let lines = parse_lrc("[00:10.00][00:30.00]Chorus\n[00:20.00]Verse");
+        let lines: Vec<(i64, &str)> = lines.iter().map(|line| (line.time, line.text.as_str())).collect();
+        assert_eq!(lines, vec![(10_000, "Chorus"), (20_000, "Verse"), (30_000, "Chorus")]);
+    }
+
+    #[test]
+    fn applies_the_offset() {
+        let lines = parse_lrc("[offset:+500]\n[00:00.20]Start\n[00:02.00]Later");
+        let times: Vec<i64> = lines.iter().map(|line| line.time).collect();
+        assert_eq!(times, vec![0, 1_500]);
+    }
+
+    #[test]
+    fn reads_latin1_sidecars() {
+        let dir = std::env::temp_dir().join(format!("bragi-lyrics-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("track.lrc"), b"[00:01.00]Caf\xe9").unwrap();
+        let lyrics = read_sidecar_lyrics(&dir.join("track.flac")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(lyrics.content, "[00:01.00]Café");
+        assert!(lyrics.synced);
+    }
+
+    #[test]
+    fn plain_text_has_no_lines() {
+        assert!(parse_lrc("Just some lyrics\nwithout timestamps").is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+use lofty::error::FileParseError;
+use lofty::file::{AudioFile, FileType, TaggedFile, TaggedFileExt};
+use lofty::probe::Probe;
+use lofty::tag::{Accessor, ItemKey, Tag};
+use service::track::AudioProperties;
+
+/// Metadata read from the tags embedded in an audio file (ID3v2, Vorbis comments, MP4 atoms, APE)
+#[derive(Default)]
+pub(crate) struct TrackMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<i32>,
+    pub disc_number: Option<i32>,
+    pub disc_total: Option<i32>,
+    pub year: Option<i32>,
+    pub composer: Option<String>,
+    pub remixer: Option<String>,
+    /// Set by the iTunes compilation flag
+    pub compilation: bool,
+    /// Duration in milliseconds, parsed from the container headers
+    pub duration: Option<i64>,
+    pub properties: AudioProperties,
+    /// ReplayGain gains in dB and peaks as linear sample values
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+    /// Unsynchronized lyrics, or LRC lines when the tag holds synced lyrics
+    pub lyrics: Option<String>,
+    /// Every value of the genre and mood tags, ID3v1 genre numbers are resolved to their names
+    pub genres: Vec<String>,
+    pub moods: Vec<String>,
+}
+
+/// Reads the embedded tags and audio properties of an audio file.
+/// The file type is guessed from the content, so misnamed extensions are still read correctly.
+pub(crate) fn read_metadata(path: &Path) -> Result<TrackMetadata, FileParseError> {
+    let tagged_file = Probe::open(path)?.guess_file_type()?.read()?;
+    let duration = Some(tagged_file.properties().duration().as_millis() as i64)
+        .filter(|duration| *duration > 0);
+    let properties = read_properties(&tagged_file);
+
+    let Some(tag) = tagged_file.primary_tag().or(tagged_file.first_tag()) else {
+        return Ok(TrackMetadata { duration, properties, ..Default::default() })
+    };
+
+    Ok(TrackMetadata {
+        title: non_empty(tag.title().map(|s| s.to_string())),
+        artist: non_empty(tag.artist().map(|s| s.to_string())),
+        album: non_empty(tag.album().map(|s| s.to_string())),
+        album_artist: non_empty(tag.get_string(ItemKey::AlbumArtist).map(|s| s.to_string())),
+        track_number: tag.track().map(|n| n as i32),
+        disc_number: tag.disk().map(|n| n as i32),
+        disc_total: tag.disk_total().map(|n| n as i32),
+        year: read_year(tag),
+        composer: non_empty(tag.get_string(ItemKey::Composer).map(|s| s.to_string())),
+        remixer: non_empty(tag.get_string(ItemKey::Remixer).map(|s| s.to_string())),
+        compilation: tag.get_string(ItemKey::FlagCompilation).is_some_and(|flag| flag == "1"),
+        duration,
+        properties,
+        track_gain: read_gain(tag, ItemKey::ReplayGainTrackGain, ItemKey::R128TrackGain),
+        track_peak: tag.get_string(ItemKey::ReplayGainTrackPeak).and_then(parse_peak),
+        album_gain: read_gain(tag, ItemKey::ReplayGainAlbumGain, ItemKey::R128AlbumGain),
+        album_peak: tag.get_string(ItemKey::ReplayGainAlbumPeak).and_then(parse_peak),
+        lyrics: non_empty(tag.get_string(ItemKey::Lyrics).or(tag.get_string(ItemKey::UnsyncLyrics)).map(|s| s.to_string())),
+        genres: tag.get_strings(ItemKey::Genre).map(|s| s.to_string()).collect(),
+        moods: tag.get_strings(ItemKey::Mood).map(|s| s.to_string()).collect(),
+    })
+}
+
+/// Reads a ReplayGain gain like `-6.48 dB`, falling back to the Opus R128 gain.
+/// R128 gains are Q7.8 fixed point numbers relative to -23 LUFS instead of the -18 LUFS ReplayGain uses.
+fn read_gain(tag: &Tag, replay_gain: ItemKey, r128_gain: ItemKey) -> Option<f64> {
+    if let Some(gain) = tag.get_string(replay_gain) {
+        return gain.trim().trim_end_matches(|c: char| c.is_alphabetic()).trim().parse().ok()
+    }
+    tag.get_string(r128_gain)
+        .and_then(|gain| gain.trim().parse::<i16>().ok())
+        .map(|gain| gain as f64 / 256.0 + 5.0)
+}
+
+fn parse_peak(peak: &str) -> Option<f64> {
+    peak.trim().parse().ok().filter(|peak: &f64| *peak >= 0.0)
+}
+
+/// Reads the codec, container and stream properties from the parsed file headers
+fn read_properties(tagged_file: &TaggedFile) -> AudioProperties {
+    let properties = tagged_file.properties();
+    let bit_depth = properties.bit_depth().map(|n| n as i32);
+    let (container, codec) = match tagged_file.file_type() {
+        FileType::Aac => ("aac", "aac"),
+        FileType::Aiff => ("aiff", "pcm"),
+        FileType::Ape => ("ape", "ape"),
+        FileType::Flac => ("flac", "flac"),
+        FileType::Mpeg => ("mpeg", "mp3"),
+        // MP4 files mostly hold AAC or ALAC, only the lossless codecs report a bit depth
+        FileType::Mp4 if bit_depth.is_some() => ("mp4", "alac"),
+        FileType::Mp4 => ("mp4", "aac"),
+        FileType::Mpc => ("mpc", "musepack"),
+        FileType::Opus => ("ogg", "opus"),
+        FileType::Vorbis => ("ogg", "vorbis"),
+        FileType::Speex => ("ogg", "speex"),
+        FileType::Wav => ("wav", "pcm"),
+        FileType::WavPack => ("wavpack", "wavpack"),
+        _ => return AudioProperties::default(),
+    };
+
+    AudioProperties {
+        codec: Some(codec.to_string()),
+        container: Some(container.to_string()),
+        bitrate: properties.audio_bitrate().filter(|bitrate| *bitrate > 0).map(|n| n as i32),
+        sample_rate: properties.sample_rate().filter(|sample_rate| *sample_rate > 0).map(|n| n as i32),
+        bit_depth,
+        channels: properties.channels().map(|n| n as i32),
+    }
+}
+
+fn read_year(tag: &Tag) -> Option<i32> {
+    if let Some(date) = tag.date() {
+        return Some(date.year as i32)
+    }
+    tag.get_string(ItemKey::ReleaseDate)
+        .and_then(|date| date.get(..4))
+        .and_then(|year| year.parse().ok())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
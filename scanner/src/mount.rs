@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::Path;
+use crate::Scanner;
+
+impl Scanner {
+    /// Checks whether the library can be scanned, returning the reason when it cannot.
+    /// An unmounted library looks like every file was removed, so scanning or pruning it would empty the database.
+    /// Mount points often stay behind as empty directories, so an empty library is unavailable while artists are stored.
+    pub(crate) async fn library_unavailable(&self) -> Option<String> {
+        let path = Path::new(&self.library_path);
+        if !path.is_dir() {
+            return Some(format!("Library path {} is unavailable", self.library_path))
+        }
+        if self.options.require_mount_point && !is_mount_point(path) {
+            return Some(format!("Library path {} is not mounted", self.library_path))
+        }
+        let empty = fs::read_dir(path).map_or(true, |mut entries| entries.next().is_none());
+        if empty && !self.artist_service.get_all().await.unwrap().is_empty() {
+            return Some(format!("Library path {} is empty, it may not be mounted", self.library_path))
+        }
+        None
+    }
+
+    /// Checks whether the path is reached through a symlink in the library whose target is missing,
+    /// like an artist folder linked in from a disk that is not mounted. Such paths are unavailable rather than removed.
+    pub(crate) fn behind_broken_symlink(&self, path: &Path) -> bool {
+        let library_path = Path::new(&self.library_path);
+        path.ancestors()
+            .take_while(|ancestor| ancestor.starts_with(library_path) && *ancestor != library_path)
+            .any(|ancestor| ancestor.is_symlink() && !ancestor.exists())
+    }
+}
+
+/// A mount point lives on another device than its parent directory
+#[cfg(unix)]
+fn is_mount_point(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(path) = path.canonicalize() else {
+        return false
+    };
+    let Some(parent) = path.parent() else {
+        return true
+    };
+    match (path.metadata(), parent.metadata()) {
+        (Ok(metadata), Ok(parent)) => metadata.dev() != parent.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_mount_point(_path: &Path) -> bool {
+    true
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use entities::{album, track};
+use service::artist::ArtistAlter;
+use service::path::stored_path;
+use crate::fingerprint::{hash_file, Fingerprint};
+use crate::{is_audio_file, Scanner};
+
+/// Tracks whose files disappeared from disk since the last scan, grouped by the path of their file.
+/// Tracks split from a CUE sheet share a file, so they move together.
+/// Every scan and watcher batch indexes its own missing tracks and passes them down to the directories it scans.
+#[derive(Default)]
+pub(crate) struct MissingTracks(Mutex<HashMap<PathBuf, Vec<track::Model>>>);
+
+impl MissingTracks {
+    /// Takes the tracks of the missing file the file on disk was renamed or moved from, keyed by their CUE track number.
+    /// The tracks keep their id, so playlists and other references to them survive the move.
+    pub fn claim(&self, path: &Path, fingerprint: &Fingerprint) -> HashMap<i32, track::Model> {
+        let mut missing_tracks = self.0.lock().unwrap();
+        let Some(old_path) = find(&missing_tracks, path, fingerprint) else {
+            return HashMap::new()
+        };
+        println!("Track moved from {}", old_path.display());
+        missing_tracks.remove(&old_path).unwrap_or_default().into_iter()
+            .map(|track| (track.cue_track, track))
+            .collect()
+    }
+}
+
+/// Finds the missing file the file on disk was renamed or moved from.
+/// Files match when their size and content hash are the same, or their size and modification time
+/// when the missing track was stored without a hash. The hash of the file on disk is only computed when needed.
+fn find(missing_tracks: &HashMap<PathBuf, Vec<track::Model>>, path: &Path, fingerprint: &Fingerprint) -> Option<PathBuf> {
+    let candidates: Vec<(&PathBuf, &track::Model)> = missing_tracks.iter()
+        .filter_map(|(old_path, tracks)| Some((old_path, tracks.first()?)))
+        .filter(|(_, track)| track.file_size == Some(fingerprint.size))
+        .collect();
+    if candidates.is_empty() {
+        return None
+    }
+    let mut content_hash = fingerprint.content_hash.clone();
+    candidates.into_iter()
+        .find(|(_, track)| match &track.content_hash {
+            Some(stored_hash) => {
+                if content_hash.is_none() {
+                    content_hash = hash_file(path).ok();
+                }
+                content_hash.as_ref() == Some(stored_hash)
+            }
+            None => track.modified_at == Some(fingerprint.modified_at),
+        })
+        .map(|(old_path, _)| old_path.clone())
+}
+
+impl Scanner {
+    /// Collects the tracks whose files no longer exist, before the scan adds the files they may have moved to
+    pub(crate) async fn index_missing_tracks(&self) -> MissingTracks {
+        let mut missing: HashMap<PathBuf, Vec<track::Model>> = HashMap::new();
+        for track in self.track_service.get_all().await.unwrap() {
+            let path = stored_path(&track.path, track.path_bytes.as_deref());
+            if !path.exists() {
+                missing.entry(path).or_default().push(track);
+            }
+        }
+        if !missing.is_empty() {
+            println!("{} track files disappeared since the last scan, looking for them under new paths", missing.len());
+        }
+        MissingTracks(Mutex::new(missing))
+    }
+
+    /// Counts the audio files below the directory that were moved from each album
+    fn count_moved_files(&self, path: &Path, max_depth: usize, missing: &MissingTracks) -> HashMap<i32, usize> {
+        if missing.0.lock().unwrap().is_empty() {
+            return HashMap::new()
+        }
+        let files: Vec<(PathBuf, Fingerprint)> = self.ignore_rules.walk(path).min_depth(1).max_depth(max_depth).into_iter()
+            .filter_entry(|entry| !self.ignore_rules.skips(entry))
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_file() && is_audio_file(entry.path()))
+            .filter_map(|entry| Some((entry.path().to_path_buf(), Fingerprint::read(entry.path(), false).ok()?)))
+            .collect();
+        let missing_tracks = missing.0.lock().unwrap();
+        let mut counts = HashMap::new();
+        for (file, fingerprint) in files {
+            if let Some(album_id) = find(&missing_tracks, &file, &fingerprint)
+                .and_then(|old_path| missing_tracks.get(&old_path))
+                .and_then(|tracks| tracks.first())
+                .map(|track| track.album_id) {
+                *counts.entry(album_id).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Finds the album whose directory disappeared and whose files now live in the album directory,
+    /// like after fixing a typo in the album name
+    pub(crate) async fn find_moved_album(&self, path: &Path, missing: &MissingTracks) -> Option<album::Model> {
+        let counts = self.count_moved_files(path, 2, missing);
+        let mut best = None;
+        for (album_id, count) in counts {
+            let Some(album) = self.album_service.get_by_id(album_id).await.unwrap() else {
+                continue;
+            };
+            if stored_path(&album.path, album.path_bytes.as_deref()).exists() {
+                continue;
+            }
+            if best.as_ref().is_none_or(|(_, best_count)| count > *best_count) {
+                best = Some((album, count));
+            }
+        }
+        best.map(|(album, _)| album)
+    }
+
+    /// Renames the artist whose directory disappeared and whose files now live in the artist directory.
+    /// Returns the id of the renamed artist, or None when the directory belongs to a new artist.
+    pub(crate) async fn adopt_moved_artist(&self, name: &str, path: &Path, missing: &MissingTracks) -> Option<i32> {
+        let counts = self.count_moved_files(path, 3, missing);
+        if counts.is_empty() || self.artist_service.get_by_exact_name(name).await.unwrap().is_some() {
+            return None
+        }
+        let mut artist_counts: HashMap<i32, usize> = HashMap::new();
+        for (album_id, count) in counts {
+            if let Some(album) = self.album_service.get_by_id(album_id).await.unwrap() {
+                *artist_counts.entry(album.artist_id).or_insert(0) += count;
+            }
+        }
+        let mut best = None;
+        for (artist_id, count) in artist_counts {
+            let Some(artist) = self.artist_service.get_by_id(artist_id).await.unwrap() else {
+                continue;
+            };
+            // Artists without a directory of their own were never moved
+            if artist.path.as_deref().is_none_or(|path| stored_path(path, artist.path_bytes.as_deref()).exists()) {
+                continue;
+            }
+            if best.as_ref().is_none_or(|(_, best_count)| count > *best_count) {
+                best = Some((artist, count));
+            }
+        }
+        let (artist, _) = best?;
+        println!("Artist renamed from {} to {}", artist.name, name);
+        self.artist_service.alter(artist.id, ArtistAlter {
+            name: Some(name.to_string()),
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        }).await.unwrap();
+        Some(artist.id)
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use entities::sea_orm_active_enums::IssueReason;
+use service::playlist::PlaylistImport;
+use crate::ignore_rules::IgnoreRules;
+use crate::{decode_text, get_filename_stem, ScanStats, Scanner};
+
+const PLAYLIST_EXTENSIONS: [&str; 3] = ["m3u", "m3u8", "pls"];
+
+/// Name and entries of a playlist file, entries are paths or URLs as they are written in the file
+struct PlaylistFile {
+    name: Option<String>,
+    entries: Vec<String>,
+}
+
+impl Scanner {
+    /// Imports the playlist files in `ScannerOptions::playlist_path`, or the library when it is not set.
+    /// Entries are matched to tracks by path, an entry pointing at a file that a CUE sheet splits adds all of its tracks.
+    /// Entries that match no track are kept on the playlist and reported.
+    /// Playlists whose file disappeared are removed, unless the playlist directory is unavailable.
+    pub(crate) async fn import_playlists(&self, stats: &ScanStats) {
+        let playlist_dir = PathBuf::from(self.options.playlist_path.as_ref().unwrap_or(&self.library_path));
+        if !playlist_dir.is_dir() {
+            println!("Playlist path {} is unavailable, skipping playlist import", playlist_dir.display());
+            return
+        }
+
+        let mut tracks_by_path: HashMap<String, Vec<(i32, i32)>> = HashMap::new();
+        for track in self.track_service.get_all().await.unwrap() {
+            tracks_by_path.entry(track.path).or_default().push((track.cue_track, track.id));
+        }
+        for tracks in tracks_by_path.values_mut() {
+            tracks.sort();
+        }
+        let lowercase_paths: HashMap<String, String> = tracks_by_path.keys()
+            .map(|path| (path.to_lowercase(), path.clone()))
+            .collect();
+
+        let mut imported = HashSet::new();
+        let mut unresolved_total = 0;
+        for path in list_playlist_files(&playlist_dir, &self.ignore_rules) {
+            let path_str = path.to_string_lossy();
+            let Some(playlist) = read_playlist(&path) else {
+                println!("Unable to read playlist {}", path.display());
+                continue;
+            };
+            let base = path.parent().unwrap_or(&playlist_dir);
+            let mut tracks = Vec::new();
+            let mut unresolved_entries = Vec::new();
+            for entry in playlist.entries {
+                let resolved = resolve_entry(&entry, base);
+                let matched = resolved.as_ref()
+                    .map(|resolved| resolved.to_string_lossy())
+                    .and_then(|resolved| tracks_by_path.get(resolved.as_ref())
+                        .or_else(|| lowercase_paths.get(&resolved.to_lowercase()).and_then(|path| tracks_by_path.get(path))));
+                match matched {
+                    Some(matched) => tracks.extend(matched.iter().map(|(_, track_id)| *track_id)),
+                    None => {
+                        stats.issue(resolved.as_deref().unwrap_or(Path::new(&entry)), IssueReason::UnresolvedPlaylistEntry);
+                        unresolved_entries.push(entry);
+                    }
+                }
+            }
+            unresolved_total += unresolved_entries.len();
+            // A playlist that fails to store keeps its previous version, so it counts as imported either way
+            imported.insert(path_str.to_string());
+            let result = self.playlist_service.upsert_imported(PlaylistImport {
+                name: playlist.name.or_else(|| get_filename_stem(&path)).unwrap_or_default(),
+                path: path_str.to_string(),
+                tracks,
+                unresolved_entries,
+            }).await;
+            if let Err(e) = result {
+                stats.error(format!("Unable to store playlist: {}, {}", path.display(), e));
+            }
+        }
+
+        let removed: Vec<i32> = self.playlist_service.get_imported().await.unwrap().into_iter()
+            .filter(|playlist| playlist.path.as_ref().is_some_and(|path| !imported.contains(path)))
+            .map(|playlist| playlist.id)
+            .collect();
+        if !removed.is_empty() {
+            self.playlist_service.delete_many(removed.clone()).await.unwrap();
+        }
+        println!("Imported {} playlists with {} unresolved entries, removed {} playlists",
+                 imported.len(), unresolved_total, removed.len());
+    }
+}
+
+fn list_playlist_files(path: &Path, rules: &IgnoreRules) -> Vec<PathBuf> {
+    rules.walk(path).min_depth(1).sort_by_file_name().into_iter()
+        .filter_entry(|entry| !rules.skips(entry))
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file() && is_playlist_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+pub(crate) fn is_playlist_file(path: &Path) -> bool {
+    let file_ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    PLAYLIST_EXTENSIONS.contains(&file_ext.as_str())
+}
+
+fn read_playlist(path: &Path) -> Option<PlaylistFile> {
+    let content = decode_text(fs::read(path).ok()?);
+    let is_pls = path.extension().and_then(|s| s.to_str()).is_some_and(|extension| extension.eq_ignore_ascii_case("pls"));
+    Some(if is_pls { parse_pls(&content) } else { parse_m3u(&content) })
+}
+
+/// Extended M3U files name the playlist with `#PLAYLIST:`, other comments like `#EXTINF` are skipped
+fn parse_m3u(content: &str) -> PlaylistFile {
+    let mut playlist = PlaylistFile { name: None, entries: Vec::new() };
+    for line in content.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+            playlist.name = Some(name.trim().to_string()).filter(|name| !name.is_empty());
+        } else if !line.starts_with('#') {
+            playlist.entries.push(line.to_string());
+        }
+    }
+    playlist
+}
+
+/// PLS files list their entries as numbered `FileN=` keys, which are not necessarily in order
+fn parse_pls(content: &str) -> PlaylistFile {
+    let mut entries: Vec<(u32, String)> = content.lines()
+        .filter_map(|line| line.trim().split_once('='))
+        .filter_map(|(key, value)| {
+            let number = key.trim().to_lowercase().strip_prefix("file")?.parse().ok()?;
+            Some((number, value.trim().to_string()))
+        })
+        .filter(|(_, value)| !value.is_empty())
+        .collect();
+    entries.sort_by_key(|(number, _)| *number);
+    PlaylistFile { name: None, entries: entries.into_iter().map(|(_, entry)| entry).collect() }
+}
+
+/// Resolves an entry to an absolute path, relative entries are relative to the playlist file.
+/// Windows separators and `file://` URLs are accepted, other URLs like internet radio streams resolve to nothing.
+fn resolve_entry(entry: &str, base: &Path) -> Option<PathBuf> {
+    let entry = match entry.strip_prefix("file://") {
+        Some(url) => percent_decode(url.strip_prefix("localhost").unwrap_or(url)),
+        None if entry.contains("://") => return None,
+        None => entry.to_string(),
+    };
+    let entry = entry.replace('\\', "/");
+    let mut resolved = PathBuf::new();
+    for component in base.join(entry).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    Some(resolved)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_extended_m3u() {
+        let playlist = parse_m3u("#EXTM3U\n#PLAYLIST: Road Trip \n#EXTINF:123,Artist - Title\nArtist/Album/01 Title.flac\n\n/music/Other.mp3\n");
+        assert_eq!(playlist.name.as_deref(), Some("Road Trip"));
+        assert_eq!(playlist.entries, vec!["Artist/Album/01 Title.flac", "/music/Other.mp3"]);
+    }
+
+    #[test]
+    fn orders_pls_entries_by_number() {
+        let playlist = parse_pls("[playlist]\nFile2=second.mp3\nTitle2=Second\nfile1=first.mp3\nFile3=\nNumberOfEntries=3\n");
+        assert_eq!(playlist.name, None);
+        assert_eq!(playlist.entries, vec!["first.mp3", "second.mp3"]);
+    }
+
+    #[test]
+    fn resolves_relative_entries_against_the_playlist() {
+        let base = Path::new("/music/playlists");
+        assert_eq!(resolve_entry("../Artist/Album/01 Title.flac", base), Some(PathBuf::from("/music/Artist/Album/01 Title.flac")));
+        assert_eq!(resolve_entry("./Artist\\Album\\02 Title.flac", base), Some(PathBuf::from("/music/playlists/Artist/Album/02 Title.flac")));
+    }
+
+    #[test]
+    fn keeps_absolute_entries() {
+        let base = Path::new("/music/playlists");
+        assert_eq!(resolve_entry("/library/Artist/Track.mp3", base), Some(PathBuf::from("/library/Artist/Track.mp3")));
+        assert_eq!(resolve_entry("file:///library/My%20Artist/Track.mp3", base), Some(PathBuf::from("/library/My Artist/Track.mp3")));
+        assert_eq!(resolve_entry("file://localhost/library/Track.mp3", base), Some(PathBuf::from("/library/Track.mp3")));
+    }
+
+    #[test]
+    fn resolves_parent_directories_and_windows_separators() {
+        let base = Path::new("/music/playlists/mixes");
+        assert_eq!(resolve_entry("..\\..\\Artist\\Album\\..\\Single.mp3", base), Some(PathBuf::from("/music/Artist/Single.mp3")));
+        assert_eq!(resolve_entry("../../../../../Track.mp3", base), Some(PathBuf::from("/Track.mp3")));
+        assert_eq!(resolve_entry("file:///music/a/../b/Track%2001.flac", base), Some(PathBuf::from("/music/b/Track 01.flac")));
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(percent_decode("My%20Artist/Caf%C3%A9"), "My Artist/Café");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("50%zz off"), "50%zz off");
+    }
+
+    #[test]
+    fn skips_urls() {
+        assert_eq!(resolve_entry("http://radio.example.com/stream", Path::new("/music")), None);
+    }
+}
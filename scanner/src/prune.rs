@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use service::path::stored_path;
+use crate::{ScanStats, Scanner};
+
+/// Rows removed from the database because their files disappeared from disk or are ignored
+#[derive(Default)]
+pub struct PruneReport {
+    pub artists: Vec<String>,
+    pub albums: Vec<String>,
+    pub tracks: Vec<String>,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.artists.is_empty() && self.albums.is_empty() && self.tracks.is_empty()
+    }
+}
+
+impl Scanner {
+    /// Removes albums and tracks whose paths no longer exist on disk or are ignored.
+    /// Albums left without tracks are removed as well. Artists are kept as long as they have albums or appear on tracks,
+    /// and artists without either are only removed once their directory is gone, so the next scan does not create them again.
+    /// Artists found in tags own albums in other artists' directories, so an artist is never removed only because of its path.
+    /// Genres no remaining track is tagged with are removed last.
+    /// Nothing is removed while the library is unavailable, paths behind a broken symlink are kept as well.
+    pub async fn prune_library(&self) -> PruneReport {
+        let mut report = PruneReport::default();
+        if let Some(reason) = self.library_unavailable().await {
+            println!("{}, skipping pruning", reason);
+            return report
+        }
+
+        let artists = self.artist_service.get_all().await.unwrap();
+        let albums = self.album_service.get_all().await.unwrap();
+        let tracks = self.track_service.get_all().await.unwrap();
+        let credits = self.track_artist_service.get_all().await.unwrap();
+
+        let mut removed_albums: HashSet<i32> = albums.iter()
+            .filter(|album| self.is_gone(&stored_path(&album.path, album.path_bytes.as_deref())))
+            .map(|album| album.id)
+            .collect();
+        let removed_tracks: HashSet<i32> = tracks.iter()
+            .filter(|track| removed_albums.contains(&track.album_id) || self.is_gone(&stored_path(&track.path, track.path_bytes.as_deref())))
+            .map(|track| track.id)
+            .collect();
+
+        let albums_with_tracks: HashSet<i32> = tracks.iter()
+            .filter(|track| !removed_tracks.contains(&track.id))
+            .map(|track| track.album_id)
+            .collect();
+        removed_albums.extend(albums.iter()
+            .filter(|album| !albums_with_tracks.contains(&album.id))
+            .map(|album| album.id));
+
+        let artists_with_albums: HashSet<i32> = albums.iter()
+            .filter(|album| !removed_albums.contains(&album.id))
+            .map(|album| album.artist_id)
+            .collect();
+        let remaining_tracks: HashSet<i32> = tracks.iter()
+            .filter(|track| !removed_tracks.contains(&track.id) && !removed_albums.contains(&track.album_id))
+            .map(|track| track.id)
+            .collect();
+        let artists_with_appearances: HashSet<i32> = credits.iter()
+            .filter(|credit| remaining_tracks.contains(&credit.track_id))
+            .map(|credit| credit.artist_id)
+            .collect();
+        let removed_artists: HashSet<i32> = artists.iter()
+            .filter(|artist| !artists_with_appearances.contains(&artist.id) && !artists_with_albums.contains(&artist.id))
+            .filter(|artist| artist.path.as_deref().is_none_or(|path| self.is_gone(&stored_path(path, artist.path_bytes.as_deref()))))
+            .map(|artist| artist.id)
+            .collect();
+
+        report.artists = artists.iter()
+            .filter(|artist| removed_artists.contains(&artist.id))
+            .map(|artist| artist.name.clone())
+            .collect();
+        report.albums = albums.iter()
+            .filter(|album| removed_albums.contains(&album.id))
+            .map(|album| album.title.clone())
+            .collect();
+        report.tracks = tracks.iter()
+            .filter(|track| removed_tracks.contains(&track.id) || removed_albums.contains(&track.album_id))
+            .map(|track| track.path.clone())
+            .collect();
+
+        // Deleting an artist deletes its albums, deleting an album deletes its tracks
+        let deleted_albums: Vec<i32> = albums.iter()
+            .filter(|album| removed_albums.contains(&album.id) && !removed_artists.contains(&album.artist_id))
+            .map(|album| album.id)
+            .collect();
+        let deleted_tracks: Vec<i32> = tracks.iter()
+            .filter(|track| removed_tracks.contains(&track.id) && !removed_albums.contains(&track.album_id))
+            .map(|track| track.id)
+            .collect();
+
+        self.artist_service.delete_many(removed_artists.into_iter().collect()).await.unwrap();
+        self.album_service.delete_many(deleted_albums).await.unwrap();
+        self.track_service.delete_many(deleted_tracks).await.unwrap();
+
+        // Albums that lost tracks lose the genres only those tracks had
+        let pruned_albums: HashSet<i32> = tracks.iter()
+            .filter(|track| removed_tracks.contains(&track.id) && !removed_albums.contains(&track.album_id))
+            .map(|track| track.album_id)
+            .collect();
+        for album_id in pruned_albums {
+            self.genre_service.refresh_album(album_id).await.unwrap();
+        }
+        self.genre_service.delete_unused().await.unwrap();
+
+        report
+    }
+
+    /// Prunes like `prune_library`, but only the albums and artists in the directories and the tracks of those albums,
+    /// so a batch of watcher events only checks the paths it touched.
+    /// Returns whether any track was removed.
+    pub(crate) async fn prune_directories(&self, directories: &[PathBuf], stats: &ScanStats) -> bool {
+        if let Some(reason) = self.library_unavailable().await {
+            println!("{}, skipping pruning", reason);
+            return false
+        }
+        let in_directories = |path: &Path| directories.iter().any(|directory| path.starts_with(directory));
+
+        let mut removed_albums = Vec::new();
+        let mut pruned_albums = Vec::new();
+        let mut removed_tracks = Vec::new();
+        let mut artist_ids = HashSet::new();
+        let mut tracks_removed = 0;
+        for album in self.album_service.get_all().await.unwrap() {
+            let album_path = stored_path(&album.path, album.path_bytes.as_deref());
+            if !in_directories(&album_path) {
+                continue;
+            }
+            let tracks = self.track_service.get_all_by_album(album.clone()).await.unwrap();
+            let album_gone = self.is_gone(&album_path);
+            let gone_tracks: Vec<i32> = tracks.iter()
+                .filter(|track| album_gone || self.is_gone(&stored_path(&track.path, track.path_bytes.as_deref())))
+                .map(|track| track.id)
+                .collect();
+            if gone_tracks.len() == tracks.len() {
+                println!("Pruning album: {}", album.title);
+                tracks_removed += tracks.len();
+                removed_albums.push(album.id);
+                artist_ids.insert(album.artist_id);
+            } else if !gone_tracks.is_empty() {
+                removed_tracks.extend(gone_tracks);
+                pruned_albums.push(album.id);
+            }
+        }
+        if !removed_tracks.is_empty() {
+            tracks_removed += self.track_service.delete_many(removed_tracks).await.unwrap() as usize;
+        }
+        ScanStats::add(&stats.tracks_removed, tracks_removed);
+        if !removed_albums.is_empty() {
+            let removed = self.album_service.delete_many(removed_albums).await.unwrap();
+            ScanStats::add(&stats.albums_removed, removed as usize);
+        }
+        for album_id in &pruned_albums {
+            self.genre_service.refresh_album(*album_id).await.unwrap();
+        }
+
+        let mut removed_artists = Vec::new();
+        for artist in self.artist_service.get_all().await.unwrap() {
+            let artist_path = artist.path.as_deref().map(|path| stored_path(path, artist.path_bytes.as_deref()));
+            if !artist_ids.contains(&artist.id) && !artist_path.as_deref().is_some_and(in_directories) {
+                continue;
+            }
+            if artist_path.as_deref().is_some_and(|path| !self.is_gone(path))
+                || self.album_service.get_by_artist_id(artist.id).await.unwrap().is_some()
+                || !self.track_artist_service.get_appearances(artist.id).await.unwrap().is_empty() {
+                continue;
+            }
+            println!("Pruning artist: {}", artist.name);
+            removed_artists.push(artist.id);
+        }
+        if !removed_artists.is_empty() {
+            let removed = self.artist_service.delete_many(removed_artists).await.unwrap();
+            ScanStats::add(&stats.artists_removed, removed as usize);
+        }
+        self.genre_service.delete_unused().await.unwrap();
+
+        tracks_removed > 0
+    }
+
+    /// Checks whether the path disappeared from disk or is ignored
+    fn is_gone(&self, path: &Path) -> bool {
+        (!path.exists() && !self.behind_broken_symlink(path)) || self.ignore_rules.is_ignored(path)
+    }
+}
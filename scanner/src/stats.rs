@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::path::Path;
+use std::sync::Mutex;
+use entities::sea_orm_active_enums::IssueReason;
+use service::scan_job::{ScanIssueCreate, ScanJobProgress};
+
+/// Counts what a scan added, updated and removed, shared between the concurrent scan workers.
+/// Also carries the cancellation flag, which the workers check before every artist, album and track.
+#[derive(Default)]
+pub struct ScanStats {
+    pub(crate) artists_total: AtomicI32,
+    pub(crate) artists_scanned: AtomicI32,
+    pub(crate) artists_added: AtomicI32,
+    pub(crate) artists_updated: AtomicI32,
+    pub(crate) artists_removed: AtomicI32,
+    pub(crate) albums_added: AtomicI32,
+    pub(crate) albums_updated: AtomicI32,
+    pub(crate) albums_removed: AtomicI32,
+    pub(crate) tracks_added: AtomicI32,
+    pub(crate) tracks_updated: AtomicI32,
+    pub(crate) tracks_removed: AtomicI32,
+    errors: Mutex<Vec<String>>,
+    /// Skipped files and directories that were not stored on the scan job yet
+    issues: Mutex<Vec<ScanIssueCreate>>,
+    cancelled: AtomicBool,
+}
+
+impl ScanStats {
+    pub(crate) fn add(counter: &AtomicI32, count: usize) {
+        counter.fetch_add(count as i32, Ordering::Relaxed);
+    }
+
+    pub(crate) fn error(&self, error: String) {
+        println!("{}", error);
+        self.errors.lock().unwrap().push(error);
+    }
+
+    pub(crate) fn issue(&self, path: &Path, reason: IssueReason) {
+        println!("Skipping {}: {:?}", path.display(), reason);
+        self.issues.lock().unwrap().push(ScanIssueCreate {
+            path: path.to_string_lossy().to_string(),
+            reason,
+        });
+    }
+
+    /// Takes the issues found since the last call
+    pub fn take_issues(&self) -> Vec<ScanIssueCreate> {
+        std::mem::take(&mut self.issues.lock().unwrap())
+    }
+
+    /// Checks whether nothing was changed, failed or skipped, the totals are not changes
+    pub(crate) fn is_empty(&self) -> bool {
+        let progress = self.progress();
+        [progress.artists_added, progress.artists_updated, progress.artists_removed,
+            progress.albums_added, progress.albums_updated, progress.albums_removed,
+            progress.tracks_added, progress.tracks_updated, progress.tracks_removed]
+            .iter()
+            .all(|count| *count == 0)
+            && progress.errors.is_empty()
+            && self.issues.lock().unwrap().is_empty()
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Snapshot of the counts, to be stored on the scan job
+    pub fn progress(&self) -> ScanJobProgress {
+        ScanJobProgress {
+            artists_total: self.artists_total.load(Ordering::Relaxed),
+            artists_scanned: self.artists_scanned.load(Ordering::Relaxed),
+            artists_added: self.artists_added.load(Ordering::Relaxed),
+            artists_updated: self.artists_updated.load(Ordering::Relaxed),
+            artists_removed: self.artists_removed.load(Ordering::Relaxed),
+            albums_added: self.albums_added.load(Ordering::Relaxed),
+            albums_updated: self.albums_updated.load(Ordering::Relaxed),
+            albums_removed: self.albums_removed.load(Ordering::Relaxed),
+            tracks_added: self.tracks_added.load(Ordering::Relaxed),
+            tracks_updated: self.tracks_updated.load(Ordering::Relaxed),
+            tracks_removed: self.tracks_removed.load(Ordering::Relaxed),
+            errors: self.errors.lock().unwrap().clone(),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use regex::{Captures, Regex};
+
+/// Templates matching the `Artist/Album (Year)/Track number. Artist - Track Name` convention
+pub const DEFAULT_TEMPLATES: [&str; 4] = [
+    "{albumartist}/{album} ({year})/{track}. {artist} - {title}",
+    "{albumartist}/{album} ({year})/{track}. {title}",
+    "{albumartist}/{album} ({year})/{track} - {artist} - {title}",
+    "{albumartist}/{album} ({year})/{track} - {title}",
+];
+
+const PLACEHOLDERS: [&str; 7] = ["artist", "albumartist", "album", "year", "disc", "track", "title"];
+
+/// Describes the library layout as `artist/album/track`, for example `{albumartist}/{year} - {album}/{disc}-{track} {title}`.
+/// The artist directory is always named after the artist, the album and track segments are compiled into matchers
+/// for the album directory name and the track filename without its extension.
+pub struct NamingTemplate {
+    template: String,
+    album: Regex,
+    track: Regex,
+}
+
+#[derive(Debug)]
+pub struct TemplateError {
+    template: String,
+    reason: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid naming template {}: {}", self.template, self.reason)
+    }
+}
+
+pub(crate) struct AlbumMatch {
+    pub title: String,
+    pub year: Option<i32>,
+}
+
+pub(crate) struct TrackMatch {
+    pub title: String,
+    pub artist: Option<String>,
+    pub track_number: Option<i32>,
+    pub disc_number: Option<i32>,
+}
+
+impl NamingTemplate {
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        Self::parse_with(template, false)
+    }
+
+    /// Parses the template, with `album_suffix` the album segment also matches directory names with text after it
+    fn parse_with(template: &str, album_suffix: bool) -> Result<Self, TemplateError> {
+        let error = |reason: &str| TemplateError { template: template.to_string(), reason: reason.to_string() };
+        let segments: Vec<&str> = template.split('/').collect();
+        let [artist, album, track] = segments.as_slice() else {
+            return Err(error("expected an artist, album and track segment separated by /"))
+        };
+        if !matches!(*artist, "{artist}" | "{albumartist}") {
+            return Err(error("the artist segment has to be {artist} or {albumartist}"))
+        }
+        if !album.contains("{album}") {
+            return Err(error("the album segment needs an {album} placeholder"))
+        }
+        if !track.contains("{title}") {
+            return Err(error("the track segment needs a {title} placeholder"))
+        }
+
+        Ok(NamingTemplate {
+            template: template.to_string(),
+            album: compile_segment(album, album_suffix).map_err(|reason| error(&reason))?,
+            track: compile_segment(track, false).map_err(|reason| error(&reason))?,
+        })
+    }
+
+    /// The default album segments accept text after the year, like `Album (1986) [Remaster]`,
+    /// as the `Album (Year)` folder naming convention they replace did
+    pub fn defaults() -> Vec<Self> {
+        DEFAULT_TEMPLATES.iter()
+            .map(|template| NamingTemplate::parse_with(template, true).unwrap())
+            .collect()
+    }
+
+    pub(crate) fn match_album(&self, name: &str) -> Option<AlbumMatch> {
+        let captures = self.album.captures(name)?;
+        Some(AlbumMatch {
+            title: text(&captures, "album")?,
+            year: number(&captures, "year"),
+        })
+    }
+
+    pub(crate) fn match_track(&self, file_stem: &str) -> Option<TrackMatch> {
+        let captures = self.track.captures(file_stem)?;
+        Some(TrackMatch {
+            title: text(&captures, "title")?,
+            artist: text(&captures, "artist"),
+            track_number: number(&captures, "track"),
+            disc_number: number(&captures, "disc"),
+        })
+    }
+}
+
+impl fmt::Display for NamingTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.template)
+    }
+}
+
+/// Compiles a template segment into an anchored regex with a named group per placeholder.
+/// Literal whitespace matches any amount of whitespace, placeholders repeated within the segment only capture once.
+/// With `suffix`, any text may follow the segment.
+fn compile_segment(segment: &str, suffix: bool) -> Result<Regex, String> {
+    let mut pattern = String::from("^");
+    let mut captured = Vec::new();
+    let mut rest = segment;
+    while !rest.is_empty() {
+        if let Some(placeholder) = rest.strip_prefix('{') {
+            let end = placeholder.find('}').ok_or("unclosed placeholder")?;
+            let name = &placeholder[..end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(format!("unknown placeholder {{{}}}", name))
+            }
+            let group = match name {
+                "year" => r"\d{4}",
+                "disc" | "track" => r"\d+",
+                _ => ".+?",
+            };
+            if captured.contains(&name) {
+                pattern.push_str(&format!("(?:{})", group));
+            } else {
+                pattern.push_str(&format!("(?P<{}>{})", name, group));
+                captured.push(name);
+            }
+            rest = &placeholder[end + 1..];
+            continue;
+        }
+        let literal_end = rest.find('{').unwrap_or(rest.len());
+        for (i, part) in rest[..literal_end].split(char::is_whitespace).enumerate() {
+            if i > 0 {
+                pattern.push_str(r"\s*");
+            }
+            pattern.push_str(&regex::escape(part));
+        }
+        rest = &rest[literal_end..];
+    }
+    if suffix {
+        pattern.push_str(".*");
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|e| e.to_string())
+}
+
+fn text(captures: &Captures, name: &str) -> Option<String> {
+    captures.name(name)
+        .map(|value| value.as_str().trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn number(captures: &Captures, name: &str) -> Option<i32> {
+    captures.name(name).and_then(|value| value.as_str().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_default_templates() {
+        let template = NamingTemplate::parse(DEFAULT_TEMPLATES[0]).unwrap();
+        let album = template.match_album("Abbey Road (1969)").unwrap();
+        assert_eq!((album.title.as_str(), album.year), ("Abbey Road", Some(1969)));
+        let track = template.match_track("01. The Beatles - Come Together").unwrap();
+        assert_eq!(track.title, "Come Together");
+        assert_eq!(track.artist.as_deref(), Some("The Beatles"));
+        assert_eq!(track.track_number, Some(1));
+        assert_eq!(track.disc_number, None);
+    }
+
+    #[test]
+    fn default_templates_accept_text_after_the_year() {
+        let template = &NamingTemplate::defaults()[0];
+        let album = template.match_album("Master of Puppets (1986) [Remaster]").unwrap();
+        assert_eq!((album.title.as_str(), album.year), ("Master of Puppets", Some(1986)));
+        let custom = NamingTemplate::parse(DEFAULT_TEMPLATES[0]).unwrap();
+        assert!(custom.match_album("Master of Puppets (1986) [Remaster]").is_none());
+    }
+
+    #[test]
+    fn matches_disc_and_track_tokens() {
+        let template = NamingTemplate::parse("{albumartist}/{year} - {album}/{disc}-{track} {title}").unwrap();
+        let album = template.match_album("1979 - The Wall").unwrap();
+        assert_eq!((album.title.as_str(), album.year), ("The Wall", Some(1979)));
+        let track = template.match_track("2-05  Comfortably Numb").unwrap();
+        assert_eq!(track.title, "Comfortably Numb");
+        assert_eq!((track.disc_number, track.track_number), (Some(2), Some(5)));
+        assert!(template.match_track("Comfortably Numb").is_none());
+    }
+
+    #[test]
+    fn year_token_needs_four_digits() {
+        let template = NamingTemplate::parse("{artist}/{album} ({year})/{title}").unwrap();
+        assert!(template.match_album("Album (69)").is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(NamingTemplate::parse("{artist}/{album}").is_err());
+        assert!(NamingTemplate::parse("{title}/{album}/{title}").is_err());
+        assert!(NamingTemplate::parse("{artist}/{year}/{title}").is_err());
+        assert!(NamingTemplate::parse("{artist}/{album}/{track}").is_err());
+        assert!(NamingTemplate::parse("{artist}/{album}/{title} {genre}").is_err());
+        assert!(NamingTemplate::parse("{artist}/{album}/{title").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use notify_debouncer_mini::notify::{Error, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use tokio::sync::mpsc;
+use crate::ignore_rules::IGNORE_FILE;
+use crate::moves::MissingTracks;
+use crate::playlist::is_playlist_file;
+use crate::{hash_artist_folder, list_directories, ScanStats, Scanner};
+
+/// Directory that has to be rescanned after a filesystem event
+#[derive(Hash, Eq, PartialEq)]
+enum RescanTarget {
+    Artist(PathBuf),
+    Album { artist: PathBuf, album: PathBuf },
+}
+
+impl Scanner {
+    /// Watches the library for filesystem changes until the watcher fails.
+    /// Bursts of events are debounced, then only the affected artist or album directories are rescanned.
+    /// A batch is applied while no scan job runs.
+    /// Files that disappeared are looked for under new paths first, then the rescanned directories are pruned when files
+    /// were removed, and playlists are imported again when playlist files changed or tracks were removed.
+    /// Batches that changed something or found issues are stored as a finished scan job.
+    /// A changed `.bragiignore` file rescans the directory it applies to and prunes the tracks it now ignores.
+    /// Artwork is only looked for again in the rescanned directories.
+    pub async fn watch(self: Arc<Self>, debounce: Duration) -> Result<(), Error> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut debouncer = new_debouncer(debounce, move |result: DebounceEventResult| {
+            let _ = sender.send(result);
+        })?;
+        debouncer.watcher().watch(Path::new(&self.library_path), RecursiveMode::Recursive)?;
+        println!("Watching {} for changes", self.library_path);
+
+        while let Some(result) = receiver.recv().await {
+            let events = match result {
+                Ok(events) => events,
+                Err(e) => {
+                    println!("Error watching library: {}", e);
+                    continue;
+                }
+            };
+            // A running scan job finishes before the batch is applied
+            let _library = self.library_lock.lock().await;
+            let ignore_files: Vec<&Path> = events.iter()
+                .map(|event| event.path.as_path())
+                .filter(|path| path.file_name().is_some_and(|name| name == IGNORE_FILE))
+                .collect();
+            if !ignore_files.is_empty() {
+                self.ignore_rules.reload();
+            }
+            let mut targets: HashSet<RescanTarget> = events.iter()
+                .filter_map(|event| self.rescan_target(&event.path))
+                .collect();
+            for ignore_file in &ignore_files {
+                targets.extend(self.ignore_file_targets(ignore_file));
+            }
+            let removed = events.iter().any(|event| !event.path.exists()) || !ignore_files.is_empty();
+            let playlists_changed = events.iter().any(|event| is_playlist_file(&event.path));
+            let missing = match removed {
+                true => self.index_missing_tracks().await,
+                false => MissingTracks::default(),
+            };
+
+            let stats = ScanStats::default();
+            let mut directories = Vec::new();
+            for target in targets {
+                match target {
+                    RescanTarget::Artist(path) => {
+                        self.rescan_artist(&path, &missing, &stats).await;
+                        directories.push(path);
+                    }
+                    RescanTarget::Album { artist, album } => {
+                        self.rescan_album(&artist, &album, &missing, &stats).await;
+                        directories.push(album);
+                    }
+                }
+            }
+            let tracks_removed = removed && self.prune_directories(&directories, &stats).await;
+            if tracks_removed || playlists_changed {
+                self.import_playlists(&stats).await;
+            }
+            self.refresh_artwork(Some(&directories)).await;
+            self.store_watcher_batch(&stats).await;
+        }
+        Ok(())
+    }
+
+    /// Maps a changed path to the artist or album directory containing it
+    fn rescan_target(&self, path: &Path) -> Option<RescanTarget> {
+        let library_path = Path::new(&self.library_path);
+        let relative = path.strip_prefix(library_path).ok()?;
+        let components: Vec<&OsStr> = relative.components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        if self.ignore_rules.is_ignored(path) {
+            return None
+        }
+
+        match components.as_slice() {
+            [] => None,
+            [artist] => Some(RescanTarget::Artist(library_path.join(artist))),
+            [artist, album, ..] => Some(RescanTarget::Album {
+                artist: library_path.join(artist),
+                album: library_path.join(artist).join(album),
+            }),
+        }
+    }
+
+    /// Maps a changed ignore file to the directories it applies to, every artist for the ignore file of the library
+    fn ignore_file_targets(&self, path: &Path) -> Vec<RescanTarget> {
+        let Some(dir) = path.parent() else {
+            return vec![]
+        };
+        if dir == Path::new(&self.library_path) {
+            return list_directories(dir, &self.ignore_rules, &ScanStats::default()).into_iter()
+                .map(|entry| RescanTarget::Artist(entry.into_path()))
+                .collect()
+        }
+        self.rescan_target(dir).into_iter().collect()
+    }
+
+    async fn rescan_artist(&self, path: &Path, missing: &MissingTracks, stats: &ScanStats) {
+        if !path.is_dir() {
+            return
+        }
+        let artist_name = path.file_name().map(|s| s.to_string_lossy()).unwrap_or_default();
+        println!("Artist directory changed: {}", path.display());
+        let artist_id = match self.adopt_moved_artist(&artist_name, path, missing).await {
+            Some(artist_id) => artist_id,
+            None => self.get_or_create_artist(&artist_name, Some(path), stats).await,
+        };
+        self.store_artist_path(&self.artist_service.get_by_id(artist_id).await.unwrap().unwrap(), path).await;
+        self.scan_artist(path, artist_id, missing, stats).await;
+        let checksum = hash_artist_folder(path, &self.ignore_rules, self.options.hash_track_contents);
+        self.store_artist_checksum(artist_id, checksum, stats).await;
+    }
+
+    /// Rescans the album, then updates the checksum of its artist so the next scan does not read the artist again
+    async fn rescan_album(&self, artist_path: &Path, path: &Path, missing: &MissingTracks, stats: &ScanStats) {
+        if !path.is_dir() {
+            return
+        }
+        let artist_name = artist_path.file_name().map(|s| s.to_string_lossy()).unwrap_or_default();
+        println!("Album directory changed: {}", path.display());
+        let artist_id = self.get_or_create_artist(&artist_name, Some(artist_path), stats).await;
+        self.store_artist_path(&self.artist_service.get_by_id(artist_id).await.unwrap().unwrap(), artist_path).await;
+        let albums_map = self.albums_map().await;
+        self.scan_album_directory(artist_path, path, artist_id, &albums_map, missing, stats).await;
+        let checksum = hash_artist_folder(artist_path, &self.ignore_rules, self.options.hash_track_contents);
+        self.store_artist_checksum(artist_id, checksum, stats).await;
+    }
+}
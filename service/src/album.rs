@@ -1,8 +1,10 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use sea_orm::*;
 use entities::album::*;
-use entities::album::Column::{Title, ArtistId};
-use entities::prelude::Album;
+use entities::album::Column::{AlbumGain, Title, ArtistId};
+use sea_orm::sea_query::OnConflict;
+use crate::path::{optional_path_columns, path_columns};
 
 pub struct AlbumService {
     db: Arc<DatabaseConnection>
@@ -10,9 +12,28 @@ pub struct AlbumService {
 
 pub struct AlbumCreate {
     pub title: String,
-    pub path: String,
+    pub path: PathBuf,
     pub release_year: i32,
-    pub artist_id: i32
+    pub artist_id: i32,
+    pub compilation: bool,
+    /// ReplayGain album gain in dB
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>
+}
+
+#[derive(Default)]
+pub struct AlbumAlter {
+    pub title: Option<String>,
+    pub path: Option<PathBuf>,
+    pub release_year: Option<i32>,
+    pub artist_id: Option<i32>,
+    pub compilation: Option<bool>,
+    /// `Some(None)` removes the cover
+    pub cover_path: Option<Option<PathBuf>>,
+    pub cover_embedded: Option<bool>,
+    /// `Some(None)` removes the gain, so the loudness analysis computes it again
+    pub album_gain: Option<Option<f64>>,
+    pub album_peak: Option<Option<f64>>
 }
 
 impl AlbumService {
@@ -20,18 +41,61 @@ impl AlbumService {
     pub fn new(db: Arc<DatabaseConnection>) -> Self { AlbumService { db } }
     
     pub async fn create(&self, create_body: AlbumCreate) -> Result<Model, DbErr> {
+        let (path, path_bytes) = path_columns(&create_body.path);
         let album = ActiveModel {
             id: NotSet,
             title: Set(create_body.title),
-            path: Set(create_body.path),
+            path: Set(path),
             release_year: Set(create_body.release_year),
-            artist_id: Set(create_body.artist_id)
+            artist_id: Set(create_body.artist_id),
+            compilation: Set(create_body.compilation),
+            cover_path: Set(None),
+            cover_embedded: Set(false),
+            album_gain: Set(create_body.album_gain),
+            album_peak: Set(create_body.album_peak),
+            path_bytes: Set(path_bytes),
+            cover_path_bytes: Set(None)
         };
         
         let album = album.insert(self.db.as_ref()).await?;
         Ok(album)
     }
 
+    /// Creates the album, or returns the existing album with the same title by the same artist.
+    /// Safe to call from concurrent scans, the unique constraint decides which insert wins.
+    pub async fn get_or_create(&self, create_body: AlbumCreate) -> Result<Model, DbErr> {
+        let (title, artist_id) = (create_body.title.clone(), create_body.artist_id);
+        let (path, path_bytes) = path_columns(&create_body.path);
+        let album = ActiveModel {
+            id: NotSet,
+            title: Set(create_body.title),
+            path: Set(path),
+            release_year: Set(create_body.release_year),
+            artist_id: Set(create_body.artist_id),
+            compilation: Set(create_body.compilation),
+            cover_path: Set(None),
+            cover_embedded: Set(false),
+            album_gain: Set(create_body.album_gain),
+            album_peak: Set(create_body.album_peak),
+            path_bytes: Set(path_bytes),
+            cover_path_bytes: Set(None)
+        };
+        Entity::insert(album)
+            .on_conflict(OnConflict::columns([ArtistId, Title]).do_nothing().to_owned())
+            .do_nothing()
+            .exec(self.db.as_ref()).await?;
+        self.get_by_artist_and_title(artist_id, &title).await?
+            .ok_or(DbErr::RecordNotFound(format!("Album {} not found", title)))
+    }
+
+    /// Finds the album with exactly this title by the artist, albums are unique by artist and title
+    pub async fn get_by_artist_and_title(&self, artist_id: i32, title: &str) -> Result<Option<Model>, DbErr> {
+        Entity::find()
+            .filter(ArtistId.eq(artist_id))
+            .filter(Title.eq(title))
+            .one(self.db.as_ref()).await
+    }
+
     pub async fn exists(&self, title: &str) -> Result<bool, DbErr> {
         let count = Entity::find()
             .filter(Title.contains(title))
@@ -54,4 +118,61 @@ impl AlbumService {
             .await
             .map(|albums| if albums.is_empty() { None } else { Some(albums) })
     }
-}
\ No newline at end of file
+
+    /// Returns the albums without a ReplayGain album gain
+    pub async fn get_without_gain(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(AlbumGain.is_null())
+            .all(self.db.as_ref())
+            .await
+    }
+
+    pub async fn alter(&self, id: i32, alter_body: AlbumAlter) -> Result<Model, DbErr> {
+        let mut album: ActiveModel = self.get_by_id(id).await?
+            .ok_or(DbErr::RecordNotFound(format!("Album {} not found", id)))?
+            .into();
+
+        if let Some(title) = alter_body.title {
+            album.title = Set(title);
+        }
+        if let Some(path) = alter_body.path {
+            let (path, path_bytes) = path_columns(&path);
+            album.path = Set(path);
+            album.path_bytes = Set(path_bytes);
+        }
+        if let Some(release_year) = alter_body.release_year {
+            album.release_year = Set(release_year);
+        }
+        if let Some(artist_id) = alter_body.artist_id {
+            album.artist_id = Set(artist_id);
+        }
+        if let Some(compilation) = alter_body.compilation {
+            album.compilation = Set(compilation);
+        }
+        if let Some(cover_path) = alter_body.cover_path {
+            let (cover_path, cover_path_bytes) = optional_path_columns(cover_path.as_deref());
+            album.cover_path = Set(cover_path);
+            album.cover_path_bytes = Set(cover_path_bytes);
+        }
+        if let Some(cover_embedded) = alter_body.cover_embedded {
+            album.cover_embedded = Set(cover_embedded);
+        }
+        if let Some(album_gain) = alter_body.album_gain {
+            album.album_gain = Set(album_gain);
+        }
+        if let Some(album_peak) = alter_body.album_peak {
+            album.album_peak = Set(album_peak);
+        }
+
+        let album = album.update(self.db.as_ref()).await?;
+        Ok(album)
+    }
+
+    pub async fn delete_many(&self, ids: Vec<i32>) -> Result<u64, DbErr> {
+        let result = Entity::delete_many()
+            .filter(entities::album::Column::Id.is_in(ids))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
@@ -1,6 +1,9 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use sea_orm::*;
+use sea_orm::sea_query::OnConflict;
 use entities::artist::{ActiveModel, Entity, Model};
+use crate::path::optional_path_columns;
 
 pub struct ArtistService {
     db: Arc<DatabaseConnection>,
@@ -8,14 +11,18 @@ pub struct ArtistService {
 
 pub struct ArtistCreate {
     pub name: String,
-    pub path: String,
+    /// Directory of the artist, None for artists only known from the tags of other artists' albums
+    pub path: Option<PathBuf>,
     pub checksum: Option<String>
 }
 
+#[derive(Default)]
 pub struct ArtistAlter {
     pub name: Option<String>,
-    pub path: Option<String>,
-    pub checksum: Option<String>
+    pub path: Option<PathBuf>,
+    pub checksum: Option<String>,
+    /// `Some(None)` removes the image
+    pub image_path: Option<Option<PathBuf>>
 }
 
 impl ArtistService {
@@ -31,22 +38,55 @@ impl ArtistService {
     }
 
     pub async fn create(&self, create_body: ArtistCreate) -> Result<Model, DbErr> {
+        let (path, path_bytes) = optional_path_columns(create_body.path.as_deref());
         let artist = ActiveModel {
             id: NotSet,
             name: Set(create_body.name),
-            path: Set(create_body.path),
+            path: Set(path),
             checksum: Set(create_body.checksum),
+            image_path: Set(None),
+            path_bytes: Set(path_bytes),
+            image_path_bytes: Set(None),
         };
         let artist = artist.insert(self.db.as_ref()).await?;
         Ok(artist)
     }
 
+    /// Creates the artist, or returns the existing artist with the same name.
+    /// Safe to call from concurrent scans, the unique name constraint decides which insert wins.
+    pub async fn get_or_create(&self, create_body: ArtistCreate) -> Result<Model, DbErr> {
+        let name = create_body.name.clone();
+        let (path, path_bytes) = optional_path_columns(create_body.path.as_deref());
+        let artist = ActiveModel {
+            id: NotSet,
+            name: Set(create_body.name),
+            path: Set(path),
+            checksum: Set(create_body.checksum),
+            image_path: Set(None),
+            path_bytes: Set(path_bytes),
+            image_path_bytes: Set(None),
+        };
+        Entity::insert(artist)
+            .on_conflict(OnConflict::column(entities::artist::Column::Name).do_nothing().to_owned())
+            .do_nothing()
+            .exec(self.db.as_ref()).await?;
+        self.get_by_exact_name(&name).await?
+            .ok_or(DbErr::RecordNotFound(format!("Artist {} not found", name)))
+    }
+
     pub async fn get_by_name(&self, name: &str) -> Result<Option<Model>, DbErr> {
         Entity::find()
             .filter(entities::artist::Column::Name.contains(name))
             .one(self.db.as_ref()).await
     }
 
+    /// Finds the artist with exactly this name, unlike `get_by_name` which matches part of the name
+    pub async fn get_by_exact_name(&self, name: &str) -> Result<Option<Model>, DbErr> {
+        Entity::find()
+            .filter(entities::artist::Column::Name.eq(name))
+            .one(self.db.as_ref()).await
+    }
+
     pub async fn exists(&self, name: &str) -> Result<bool, DbErr> {
         let count = Entity::find()
             .filter(entities::artist::Column::Name.contains(name))
@@ -61,13 +101,28 @@ impl ArtistService {
             artist.name = Set(name);
         }
         if let Some(path) = alter_body.path {
+            let (path, path_bytes) = optional_path_columns(Some(&path));
             artist.path = Set(path);
+            artist.path_bytes = Set(path_bytes);
         }
         if let Some(checksum) = alter_body.checksum {
             artist.checksum = Set(Some(checksum));
         }
+        if let Some(image_path) = alter_body.image_path {
+            let (image_path, image_path_bytes) = optional_path_columns(image_path.as_deref());
+            artist.image_path = Set(image_path);
+            artist.image_path_bytes = Set(image_path_bytes);
+        }
 
         let artist = artist.update(self.db.as_ref()).await?;
         Ok(artist)
     }
-}
\ No newline at end of file
+
+    pub async fn delete_many(&self, ids: Vec<i32>) -> Result<u64, DbErr> {
+        let result = Entity::delete_many()
+            .filter(entities::artist::Column::Id.is_in(ids))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
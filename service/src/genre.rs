@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use sea_orm::*;
+use sea_orm::sea_query::OnConflict;
+use entities::genre::*;
+use entities::{album, album_genre, track_genre};
+use entities::sea_orm_active_enums::GenreKind;
+
+pub struct GenreService {
+    db: Arc<DatabaseConnection>
+}
+
+#[derive(Clone)]
+pub struct GenreCreate {
+    pub name: String,
+    pub kind: GenreKind
+}
+
+pub struct TrackGenreCreate {
+    pub track_id: i32,
+    pub genre_id: i32
+}
+
+/// Folds the name to lowercase letters and digits, so `Hip-Hop`, `Hip Hop` and `hiphop` are the same genre
+pub fn genre_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+impl GenreService {
+
+    pub fn new(db: Arc<DatabaseConnection>) -> Self { GenreService { db } }
+
+    pub async fn get_all(&self, kind: Option<GenreKind>) -> Result<Vec<Model>, DbErr> {
+        let mut query = Entity::find();
+        if let Some(kind) = kind {
+            query = query.filter(Column::Kind.eq(kind));
+        }
+        query.order_by_asc(Column::Name).all(self.db.as_ref()).await
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<Model>, DbErr> {
+        Entity::find_by_id(id).one(self.db.as_ref()).await
+    }
+
+    /// Returns the number of albums linked to each genre
+    pub async fn count_albums(&self) -> Result<HashMap<i32, i64>, DbErr> {
+        let counts: Vec<(i32, i64)> = album_genre::Entity::find()
+            .select_only()
+            .column(album_genre::Column::GenreId)
+            .column_as(album_genre::Column::Id.count(), "count")
+            .group_by(album_genre::Column::GenreId)
+            .into_tuple()
+            .all(self.db.as_ref())
+            .await?;
+        Ok(counts.into_iter().collect())
+    }
+
+    pub async fn get_albums(&self, genre_id: i32) -> Result<Vec<album::Model>, DbErr> {
+        album::Entity::find()
+            .inner_join(album_genre::Entity)
+            .filter(album_genre::Column::GenreId.eq(genre_id))
+            .order_by_asc(album::Column::Title)
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Returns the genres, creating the ones that do not exist yet.
+    /// Names with the same key are the same genre, which keeps the spelling it was first seen with.
+    pub async fn get_or_create_many(&self, create_bodies: Vec<GenreCreate>) -> Result<Vec<Model>, DbErr> {
+        if create_bodies.is_empty() {
+            return Ok(vec![])
+        }
+        let keys: Vec<String> = create_bodies.iter().map(|create_body| genre_key(&create_body.name)).collect();
+        let genres = create_bodies.into_iter().zip(keys.clone()).map(|(create_body, key)| ActiveModel {
+            id: NotSet,
+            name: Set(create_body.name),
+            key: Set(key),
+            kind: Set(create_body.kind)
+        });
+        Entity::insert_many(genres)
+            .on_conflict(
+                OnConflict::columns([Column::Key, Column::Kind])
+                    .do_nothing()
+                    .to_owned()
+            )
+            .do_nothing()
+            .exec_without_returning(self.db.as_ref())
+            .await?;
+        Entity::find()
+            .filter(Column::Key.is_in(keys))
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Replaces the genres of the tracks, tracks without new genres lose their genres
+    pub async fn replace_for_tracks(&self, track_ids: Vec<i32>, create_bodies: Vec<TrackGenreCreate>) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        track_genre::Entity::delete_many()
+            .filter(track_genre::Column::TrackId.is_in(track_ids))
+            .exec(&txn)
+            .await?;
+        if !create_bodies.is_empty() {
+            let track_genres = create_bodies.into_iter().map(|create_body| track_genre::ActiveModel {
+                id: NotSet,
+                track_id: Set(create_body.track_id),
+                genre_id: Set(create_body.genre_id)
+            });
+            track_genre::Entity::insert_many(track_genres).exec_without_returning(&txn).await?;
+        }
+        txn.commit().await
+    }
+
+    /// Links the album to every genre of its tracks
+    pub async fn refresh_album(&self, album_id: i32) -> Result<(), DbErr> {
+        let genre_ids: Vec<i32> = track_genre::Entity::find()
+            .select_only()
+            .column(track_genre::Column::GenreId)
+            .distinct()
+            .inner_join(entities::track::Entity)
+            .filter(entities::track::Column::AlbumId.eq(album_id))
+            .into_tuple()
+            .all(self.db.as_ref())
+            .await?;
+        let txn = self.db.begin().await?;
+        album_genre::Entity::delete_many()
+            .filter(album_genre::Column::AlbumId.eq(album_id))
+            .exec(&txn)
+            .await?;
+        if !genre_ids.is_empty() {
+            let album_genres = genre_ids.into_iter().map(|genre_id| album_genre::ActiveModel {
+                id: NotSet,
+                album_id: Set(album_id),
+                genre_id: Set(genre_id)
+            });
+            album_genre::Entity::insert_many(album_genres).exec_without_returning(&txn).await?;
+        }
+        txn.commit().await
+    }
+
+    /// Removes genres no track is linked to anymore
+    pub async fn delete_unused(&self) -> Result<u64, DbErr> {
+        let used: Vec<i32> = track_genre::Entity::find()
+            .select_only()
+            .column(track_genre::Column::GenreId)
+            .distinct()
+            .into_tuple()
+            .all(self.db.as_ref())
+            .await?;
+        Entity::delete_many()
+            .filter(Column::Id.is_not_in(used))
+            .exec(self.db.as_ref())
+            .await
+            .map(|result| result.rows_affected)
+    }
+}
@@ -1,7 +1,9 @@
 pub mod artist;
 pub mod album;
+pub mod genre;
+pub mod lyrics;
+pub mod path;
+pub mod playlist;
 pub mod track;
-
-fn main() {
-    println!("Hello, world!");
-}
+pub mod track_artist;
+pub mod scan_job;
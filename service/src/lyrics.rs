@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use sea_orm::*;
+use entities::lyrics::*;
+use entities::sea_orm_active_enums::LyricsSource;
+
+pub struct LyricsService {
+    db: Arc<DatabaseConnection>
+}
+
+pub struct LyricsCreate {
+    pub track_id: i32,
+    /// Plain text, or LRC formatted lines like `[01:23.45]Line` when synced
+    pub content: String,
+    pub synced: bool,
+    pub source: LyricsSource
+}
+
+impl LyricsService {
+
+    pub fn new(db: Arc<DatabaseConnection>) -> Self { LyricsService { db } }
+
+    pub async fn get_by_track_id(&self, track_id: i32) -> Result<Option<Model>, DbErr> {
+        Entity::find()
+            .filter(Column::TrackId.eq(track_id))
+            .one(self.db.as_ref())
+            .await
+    }
+
+    pub async fn get_by_track_ids(&self, track_ids: Vec<i32>) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(Column::TrackId.is_in(track_ids))
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Replaces the lyrics of the tracks, tracks without new lyrics lose their stored lyrics
+    pub async fn replace_for_tracks(&self, track_ids: Vec<i32>, create_bodies: Vec<LyricsCreate>) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        Entity::delete_many()
+            .filter(Column::TrackId.is_in(track_ids))
+            .exec(&txn)
+            .await?;
+        if !create_bodies.is_empty() {
+            let lyrics = create_bodies.into_iter().map(|create_body| ActiveModel {
+                id: NotSet,
+                track_id: Set(create_body.track_id),
+                content: Set(create_body.content),
+                synced: Set(create_body.synced),
+                source: Set(create_body.source)
+            });
+            Entity::insert_many(lyrics).exec_without_returning(&txn).await?;
+        }
+        txn.commit().await
+    }
+}
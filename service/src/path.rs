@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+
+/// Splits a path into the text stored in a `path` column and the raw bytes stored in its `path_bytes` column.
+/// The bytes are only kept for paths that are not valid UTF-8, the text then holds a lossy version for display.
+pub fn path_columns(path: &Path) -> (String, Option<Vec<u8>>) {
+    match path.to_str() {
+        Some(path) => (path.to_string(), None),
+        None => (path.to_string_lossy().to_string(), Some(path.as_os_str().as_encoded_bytes().to_vec())),
+    }
+}
+
+/// Splits an optional path like `path_columns`, for nullable `path` columns
+pub fn optional_path_columns(path: Option<&Path>) -> (Option<String>, Option<Vec<u8>>) {
+    match path.map(path_columns) {
+        Some((path, path_bytes)) => (Some(path), path_bytes),
+        None => (None, None),
+    }
+}
+
+/// Restores the path stored by `path_columns`
+pub fn stored_path(path: &str, path_bytes: Option<&[u8]>) -> PathBuf {
+    match path_bytes {
+        Some(bytes) => path_from_bytes(bytes),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_utf8_paths_as_text() {
+        let path = Path::new("/music/Café/01 Intro.flac");
+        let (text, bytes) = path_columns(path);
+        assert_eq!(text, "/music/Café/01 Intro.flac");
+        assert_eq!(bytes, None);
+        assert_eq!(stored_path(&text, bytes.as_deref()), path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restores_paths_that_are_not_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/music/caf\xe9/01.flac"));
+        let (text, bytes) = path_columns(path);
+        assert_eq!(text, "/music/caf\u{fffd}/01.flac");
+        assert_eq!(bytes.as_deref(), Some(&b"/music/caf\xe9/01.flac"[..]));
+        assert_eq!(stored_path(&text, bytes.as_deref()), path);
+    }
+
+    #[test]
+    fn keeps_missing_optional_paths_empty() {
+        assert_eq!(optional_path_columns(None), (None, None));
+        assert_eq!(optional_path_columns(Some(Path::new("/music/cover.jpg"))), (Some("/music/cover.jpg".to_string()), None));
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use sea_orm::*;
+use sea_orm::sea_query::OnConflict;
+use entities::playlist::*;
+
+pub struct PlaylistService {
+    db: Arc<DatabaseConnection>
+}
+
+/// Playlist imported from a playlist file in the library
+pub struct PlaylistImport {
+    pub name: String,
+    /// Path of the playlist file the playlist is imported from
+    pub path: String,
+    pub tracks: Vec<i32>,
+    /// Entries of the playlist file that did not match a track, as they are written in the file
+    pub unresolved_entries: Vec<String>
+}
+
+impl PlaylistService {
+
+    pub fn new(db: Arc<DatabaseConnection>) -> Self { PlaylistService { db } }
+
+    pub async fn get_all(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .order_by_asc(Column::Name)
+            .all(self.db.as_ref())
+            .await
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<Model>, DbErr> {
+        Entity::find_by_id(id).one(self.db.as_ref()).await
+    }
+
+    /// Returns the playlists imported from playlist files
+    pub async fn get_imported(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(Column::Path.is_not_null())
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Creates the playlist, or replaces the name and entries of the playlist imported from the same file
+    pub async fn upsert_imported(&self, import: PlaylistImport) -> Result<(), DbErr> {
+        let playlist = ActiveModel {
+            id: NotSet,
+            name: Set(import.name),
+            user_id: Set(None),
+            tracks: Set(import.tracks),
+            path: Set(Some(import.path)),
+            unresolved_entries: Set(import.unresolved_entries)
+        };
+        Entity::insert(playlist)
+            .on_conflict(
+                OnConflict::column(Column::Path)
+                    .update_columns([Column::Name, Column::Tracks, Column::UnresolvedEntries])
+                    .to_owned()
+            )
+            .exec_without_returning(self.db.as_ref())
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn delete_many(&self, ids: Vec<i32>) -> Result<DeleteResult, DbErr> {
+        Entity::delete_many()
+            .filter(Column::Id.is_in(ids))
+            .exec(self.db.as_ref())
+            .await
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use chrono::Utc;
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use entities::scan_job::*;
+use entities::scan_issue;
+use entities::sea_orm_active_enums::{IssueReason, ScanState};
+
+pub struct ScanJobService {
+    db: Arc<DatabaseConnection>
+}
+
+/// Counts of the artists, albums and tracks a scan job went through so far
+#[derive(Default)]
+pub struct ScanJobProgress {
+    pub artists_total: i32,
+    pub artists_scanned: i32,
+    pub artists_added: i32,
+    pub artists_updated: i32,
+    pub artists_removed: i32,
+    pub albums_added: i32,
+    pub albums_updated: i32,
+    pub albums_removed: i32,
+    pub tracks_added: i32,
+    pub tracks_updated: i32,
+    pub tracks_removed: i32,
+    pub errors: Vec<String>
+}
+
+/// A file or directory the scanner skipped or could only partially read
+pub struct ScanIssueCreate {
+    pub path: String,
+    pub reason: IssueReason,
+}
+
+impl ScanJobService {
+
+    pub fn new(db: Arc<DatabaseConnection>) -> Self { ScanJobService { db } }
+
+    /// Creates a running scan job, started now
+    pub async fn create(&self) -> Result<Model, DbErr> {
+        let scan_job = ActiveModel {
+            state: Set(ScanState::Running),
+            started_at: Set(Utc::now().fixed_offset()),
+            finished_at: Set(None),
+            errors: Set(JsonValue::Array(vec![])),
+            ..Default::default()
+        };
+
+        scan_job.insert(self.db.as_ref()).await
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .order_by_desc(Column::StartedAt)
+            .all(self.db.as_ref())
+            .await
+    }
+
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<Model>, DbErr> {
+        Entity::find_by_id(id).one(self.db.as_ref()).await
+    }
+
+    /// Stores the progress of a running scan job
+    pub async fn update_progress(&self, id: i32, progress: ScanJobProgress) -> Result<Model, DbErr> {
+        let mut scan_job: ActiveModel = self.find(id).await?.into();
+        Self::set_progress(&mut scan_job, progress);
+        scan_job.update(self.db.as_ref()).await
+    }
+
+    /// Stores the final progress and state of a scan job, finished now
+    pub async fn finish(&self, id: i32, state: ScanState, progress: ScanJobProgress) -> Result<Model, DbErr> {
+        let mut scan_job: ActiveModel = self.find(id).await?.into();
+        Self::set_progress(&mut scan_job, progress);
+        scan_job.state = Set(state);
+        scan_job.finished_at = Set(Some(Utc::now().fixed_offset()));
+        scan_job.update(self.db.as_ref()).await
+    }
+
+    /// Marks the jobs still running as failed, finished now.
+    /// No job runs before the server starts, so these were interrupted by a crash or restart.
+    pub async fn fail_stale(&self) -> Result<u64, DbErr> {
+        let result = Entity::update_many()
+            .col_expr(Column::State, Expr::value(ScanState::Failed))
+            .col_expr(Column::FinishedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(Column::State.eq(ScanState::Running))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn add_issues(&self, id: i32, issues: Vec<ScanIssueCreate>) -> Result<(), DbErr> {
+        if issues.is_empty() {
+            return Ok(())
+        }
+        let issues = issues.into_iter().map(|issue| scan_issue::ActiveModel {
+            scan_job_id: Set(id),
+            path: Set(issue.path),
+            reason: Set(issue.reason),
+            ..Default::default()
+        });
+        scan_issue::Entity::insert_many(issues).exec(self.db.as_ref()).await?;
+        Ok(())
+    }
+
+    pub async fn get_issues(&self, id: i32) -> Result<Vec<scan_issue::Model>, DbErr> {
+        scan_issue::Entity::find()
+            .filter(scan_issue::Column::ScanJobId.eq(id))
+            .order_by_asc(scan_issue::Column::Path)
+            .all(self.db.as_ref())
+            .await
+    }
+
+    async fn find(&self, id: i32) -> Result<Model, DbErr> {
+        self.get_by_id(id).await?
+            .ok_or(DbErr::RecordNotFound(format!("Scan job {} not found", id)))
+    }
+
+    fn set_progress(scan_job: &mut ActiveModel, progress: ScanJobProgress) {
+        scan_job.artists_total = Set(progress.artists_total);
+        scan_job.artists_scanned = Set(progress.artists_scanned);
+        scan_job.artists_added = Set(progress.artists_added);
+        scan_job.artists_updated = Set(progress.artists_updated);
+        scan_job.artists_removed = Set(progress.artists_removed);
+        scan_job.albums_added = Set(progress.albums_added);
+        scan_job.albums_updated = Set(progress.albums_updated);
+        scan_job.albums_removed = Set(progress.albums_removed);
+        scan_job.tracks_added = Set(progress.tracks_added);
+        scan_job.tracks_updated = Set(progress.tracks_updated);
+        scan_job.tracks_removed = Set(progress.tracks_removed);
+        scan_job.errors = Set(JsonValue::Array(progress.errors.into_iter().map(JsonValue::String).collect()));
+    }
+}
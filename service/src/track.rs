@@ -1,18 +1,87 @@
 use sea_orm::*;
+use std::path::PathBuf;
 use std::sync::Arc;
 use entities::album::Model as AlbumModel;
 use entities::track::*;
-use entities::track::Column::AlbumId;
+use entities::track::Column::{AcousticFingerprint, AcousticFingerprintKey, AlbumId, BitDepth, Codec, Container, DiscNumber, DuplicateOf, Duration, SampleRate, TrackGain, TrackNumber};
+use sea_orm::sea_query::{Expr, OnConflict};
+use crate::path::path_columns;
+/// Codecs that store audio without loss, every other codec is considered lossy
+pub const LOSSLESS_CODECS: [&str; 6] = ["flac", "alac", "pcm", "ape", "wavpack", "dsd"];
+
 pub struct TrackService {
     db: Arc<DatabaseConnection>
 }
 
 pub struct TrackCreate {
     pub title: String,
-    pub path: String,
+    pub path: PathBuf,
     pub track_number: i32,
-    pub duration: i32,
-    pub album_id: i32
+    /// Duration in milliseconds, None when it could not be read
+    pub duration: Option<i64>,
+    pub disc_number: i32,
+    pub disc_total: Option<i32>,
+    pub album_id: i32,
+    pub file_size: i64,
+    pub modified_at: i64,
+    pub content_hash: Option<String>,
+    pub properties: AudioProperties,
+    /// ReplayGain track gain in dB
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    /// Number of the track in the CUE sheet it was split from, 0 for tracks that are a file of their own
+    pub cue_track: i32,
+    /// Start and end of the track within its file in milliseconds, for tracks split from a CUE sheet
+    pub start_offset: Option<i64>,
+    pub end_offset: Option<i64>
+}
+
+#[derive(Default)]
+pub struct TrackAlter {
+    pub title: Option<String>,
+    pub path: Option<PathBuf>,
+    pub track_number: Option<i32>,
+    /// `Some(None)` marks the duration as unknown
+    pub duration: Option<Option<i64>>,
+    pub disc_number: Option<i32>,
+    pub disc_total: Option<i32>,
+    pub album_id: Option<i32>,
+    pub file_size: Option<i64>,
+    pub modified_at: Option<i64>,
+    pub content_hash: Option<String>,
+    pub properties: Option<AudioProperties>,
+    /// `Some(None)` removes the gain, so the loudness analysis computes it again
+    pub track_gain: Option<Option<f64>>,
+    pub track_peak: Option<Option<f64>>,
+    pub start_offset: Option<Option<i64>>,
+    pub end_offset: Option<Option<i64>>
+}
+
+/// Technical properties of the audio stream, all optional since not every container reports them
+#[derive(Default, Clone)]
+pub struct AudioProperties {
+    pub codec: Option<String>,
+    pub container: Option<String>,
+    /// Audio bitrate in kbps
+    pub bitrate: Option<i32>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<i32>,
+    pub bit_depth: Option<i32>,
+    pub channels: Option<i32>,
+}
+
+/// Filters for listing tracks, unset filters match every track
+#[derive(Default)]
+pub struct TrackFilter {
+    pub codec: Option<String>,
+    pub container: Option<String>,
+    pub lossless: Option<bool>,
+    pub min_sample_rate: Option<i32>,
+    pub min_bit_depth: Option<i32>,
+    /// Leaves out tracks that are a lesser copy of another track
+    pub hide_duplicates: Option<bool>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
 }
 
 impl TrackService {
@@ -30,13 +99,34 @@ impl TrackService {
     }
     
     pub async fn create(&self, create_body: TrackCreate) -> Result<Model, DbErr> {
+        let (path, path_bytes) = path_columns(&create_body.path);
         let track = ActiveModel {
             id: NotSet,
             title: Set(create_body.title),
-            path: Set(create_body.path),
+            path: Set(path),
             track_number: Set(create_body.track_number),
             duration: Set(create_body.duration),
-            album_id: Set(create_body.album_id)
+            disc_number: Set(create_body.disc_number),
+            disc_total: Set(create_body.disc_total),
+            album_id: Set(create_body.album_id),
+            file_size: Set(Some(create_body.file_size)),
+            modified_at: Set(Some(create_body.modified_at)),
+            content_hash: Set(create_body.content_hash),
+            codec: Set(create_body.properties.codec),
+            container: Set(create_body.properties.container),
+            bitrate: Set(create_body.properties.bitrate),
+            sample_rate: Set(create_body.properties.sample_rate),
+            bit_depth: Set(create_body.properties.bit_depth),
+            channels: Set(create_body.properties.channels),
+            track_gain: Set(create_body.track_gain),
+            track_peak: Set(create_body.track_peak),
+            cue_track: Set(create_body.cue_track),
+            start_offset: Set(create_body.start_offset),
+            end_offset: Set(create_body.end_offset),
+            duplicate_of: NotSet,
+            path_bytes: Set(path_bytes),
+            acoustic_fingerprint: NotSet,
+            acoustic_fingerprint_key: NotSet
         };
         
         let track = track.insert(self.db.as_ref()).await?;
@@ -44,9 +134,67 @@ impl TrackService {
         Ok(track)
     }
     
+    /// Inserts the tracks in a single statement, skipping tracks whose path and CUE track are already stored.
+    /// Returns the number of inserted tracks.
+    pub async fn create_many(&self, create_bodies: Vec<TrackCreate>) -> Result<u64, DbErr> {
+        let tracks = create_bodies.into_iter().map(|create_body| {
+            let (path, path_bytes) = path_columns(&create_body.path);
+            ActiveModel {
+                id: NotSet,
+                title: Set(create_body.title),
+                path: Set(path),
+                track_number: Set(create_body.track_number),
+                duration: Set(create_body.duration),
+                disc_number: Set(create_body.disc_number),
+                disc_total: Set(create_body.disc_total),
+                album_id: Set(create_body.album_id),
+                file_size: Set(Some(create_body.file_size)),
+                modified_at: Set(Some(create_body.modified_at)),
+                content_hash: Set(create_body.content_hash),
+                codec: Set(create_body.properties.codec),
+                container: Set(create_body.properties.container),
+                bitrate: Set(create_body.properties.bitrate),
+                sample_rate: Set(create_body.properties.sample_rate),
+                bit_depth: Set(create_body.properties.bit_depth),
+                channels: Set(create_body.properties.channels),
+                track_gain: Set(create_body.track_gain),
+                track_peak: Set(create_body.track_peak),
+                cue_track: Set(create_body.cue_track),
+                start_offset: Set(create_body.start_offset),
+                end_offset: Set(create_body.end_offset),
+                duplicate_of: NotSet,
+                path_bytes: Set(path_bytes),
+                acoustic_fingerprint: NotSet,
+                acoustic_fingerprint_key: NotSet
+            }
+        });
+
+        let result = Entity::insert_many(tracks)
+            // Targets the unique index over path, path bytes and CUE track, so other constraint violations still fail
+            .on_conflict(
+                OnConflict::new()
+                    .expr(Expr::col(Column::Path))
+                    .expr(Expr::cust(r#"coalesce("path_bytes", ''::bytea)"#))
+                    .expr(Expr::col(Column::CueTrack))
+                    .do_nothing()
+                    .to_owned()
+            )
+            .do_nothing()
+            .exec_without_returning(self.db.as_ref())
+            .await?;
+
+        Ok(match result {
+            TryInsertResult::Inserted(rows) => rows,
+            TryInsertResult::Empty | TryInsertResult::Conflicted => 0,
+        })
+    }
+
+    /// Returns the tracks of the album ordered by disc, then track number
     pub async fn get_by_album_id(&self, album_id: i32) -> Result<Option<Vec<Model>>, DbErr> {
         Entity::find()
             .filter(AlbumId.eq(album_id))
+            .order_by_asc(DiscNumber)
+            .order_by_asc(TrackNumber)
             .all(self.db.as_ref())
             .await
             .map(|tracks| if tracks.is_empty() { None } else { Some(tracks) })
@@ -55,4 +203,174 @@ impl TrackService {
     pub async fn get_by_id(&self, id: i32) -> Result<Option<Model>, DbErr> {
         Entity::find_by_id(id).one(self.db.as_ref()).await
     }
-}
\ No newline at end of file
+
+    pub async fn get_by_ids(&self, ids: Vec<i32>) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(Column::Id.is_in(ids))
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Returns the tracks stored before their duration was read
+    pub async fn get_without_duration(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(Duration.is_null())
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Returns the tracks stored before their audio properties were read
+    pub async fn get_without_codec(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(Codec.is_null())
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Returns the tracks without a ReplayGain track gain
+    pub async fn get_without_gain(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(TrackGain.is_null())
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Returns the tracks matching the filter, ordered by ID
+    pub async fn get_filtered(&self, filter: TrackFilter) -> Result<Vec<Model>, DbErr> {
+        let mut query = Entity::find();
+        if let Some(codec) = filter.codec {
+            query = query.filter(Codec.eq(codec.to_lowercase()));
+        }
+        if let Some(container) = filter.container {
+            query = query.filter(Container.eq(container.to_lowercase()));
+        }
+        match filter.lossless {
+            Some(true) => query = query.filter(Codec.is_in(LOSSLESS_CODECS)),
+            Some(false) => query = query.filter(Codec.is_not_in(LOSSLESS_CODECS)),
+            None => {}
+        }
+        if let Some(min_sample_rate) = filter.min_sample_rate {
+            query = query.filter(SampleRate.gte(min_sample_rate));
+        }
+        if let Some(min_bit_depth) = filter.min_bit_depth {
+            query = query.filter(BitDepth.gte(min_bit_depth));
+        }
+        if filter.hide_duplicates == Some(true) {
+            query = query.filter(DuplicateOf.is_null());
+        }
+        query
+            .order_by_asc(Column::Id)
+            .offset(filter.offset)
+            .limit(filter.limit)
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Returns the tracks that are a lesser copy of another track
+    pub async fn get_duplicates(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find()
+            .filter(DuplicateOf.is_not_null())
+            .order_by_asc(Column::Id)
+            .all(self.db.as_ref())
+            .await
+    }
+
+    /// Replaces the duplicate groups, every group is the preferred track and its lesser copies
+    pub async fn replace_duplicates(&self, groups: Vec<(i32, Vec<i32>)>) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        Entity::update_many()
+            .col_expr(DuplicateOf, Expr::value(Option::<i32>::None))
+            .filter(DuplicateOf.is_not_null())
+            .exec(&txn)
+            .await?;
+        for (preferred, copies) in groups {
+            Entity::update_many()
+                .col_expr(DuplicateOf, Expr::value(preferred))
+                .filter(Column::Id.is_in(copies))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await
+    }
+
+    /// Stores the Chromaprint fingerprint of the track with the key of the file version it was computed from
+    pub async fn store_acoustic_fingerprint(&self, id: i32, key: String, fingerprint: Vec<u8>) -> Result<(), DbErr> {
+        Entity::update_many()
+            .col_expr(AcousticFingerprint, Expr::value(fingerprint))
+            .col_expr(AcousticFingerprintKey, Expr::value(key))
+            .filter(Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn alter(&self, id: i32, alter_body: TrackAlter) -> Result<Model, DbErr> {
+        let mut track: ActiveModel = self.get_by_id(id).await?
+            .ok_or(DbErr::RecordNotFound(format!("Track {} not found", id)))?
+            .into();
+
+        if let Some(title) = alter_body.title {
+            track.title = Set(title);
+        }
+        if let Some(path) = alter_body.path {
+            let (path, path_bytes) = path_columns(&path);
+            track.path = Set(path);
+            track.path_bytes = Set(path_bytes);
+        }
+        if let Some(track_number) = alter_body.track_number {
+            track.track_number = Set(track_number);
+        }
+        if let Some(duration) = alter_body.duration {
+            track.duration = Set(duration);
+        }
+        if let Some(disc_number) = alter_body.disc_number {
+            track.disc_number = Set(disc_number);
+        }
+        if let Some(disc_total) = alter_body.disc_total {
+            track.disc_total = Set(Some(disc_total));
+        }
+        if let Some(album_id) = alter_body.album_id {
+            track.album_id = Set(album_id);
+        }
+        if let Some(file_size) = alter_body.file_size {
+            track.file_size = Set(Some(file_size));
+        }
+        if let Some(modified_at) = alter_body.modified_at {
+            track.modified_at = Set(Some(modified_at));
+        }
+        if let Some(content_hash) = alter_body.content_hash {
+            track.content_hash = Set(Some(content_hash));
+        }
+        if let Some(properties) = alter_body.properties {
+            track.codec = Set(properties.codec);
+            track.container = Set(properties.container);
+            track.bitrate = Set(properties.bitrate);
+            track.sample_rate = Set(properties.sample_rate);
+            track.bit_depth = Set(properties.bit_depth);
+            track.channels = Set(properties.channels);
+        }
+        if let Some(track_gain) = alter_body.track_gain {
+            track.track_gain = Set(track_gain);
+        }
+        if let Some(track_peak) = alter_body.track_peak {
+            track.track_peak = Set(track_peak);
+        }
+        if let Some(start_offset) = alter_body.start_offset {
+            track.start_offset = Set(start_offset);
+        }
+        if let Some(end_offset) = alter_body.end_offset {
+            track.end_offset = Set(end_offset);
+        }
+
+        let track = track.update(self.db.as_ref()).await?;
+        Ok(track)
+    }
+
+    pub async fn delete_many(&self, ids: Vec<i32>) -> Result<u64, DbErr> {
+        let result = Entity::delete_many()
+            .filter(entities::track::Column::Id.is_in(ids))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
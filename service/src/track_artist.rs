@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use sea_orm::*;
+use entities::track_artist::*;
+use entities::{album, artist, track};
+use entities::sea_orm_active_enums::ArtistRole;
+
+pub struct TrackArtistService {
+    db: Arc<DatabaseConnection>
+}
+
+pub struct TrackArtistCreate {
+    pub track_id: i32,
+    pub artist_id: i32,
+    pub role: ArtistRole
+}
+
+impl TrackArtistService {
+
+    pub fn new(db: Arc<DatabaseConnection>) -> Self { TrackArtistService { db } }
+
+    pub async fn get_all(&self) -> Result<Vec<Model>, DbErr> {
+        Entity::find().all(self.db.as_ref()).await
+    }
+
+    /// Returns the credits of the tracks together with the credited artists
+    pub async fn get_by_track_ids(&self, track_ids: Vec<i32>) -> Result<Vec<(Model, artist::Model)>, DbErr> {
+        let credits = Entity::find()
+            .filter(Column::TrackId.is_in(track_ids))
+            .find_also_related(artist::Entity)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(credits.into_iter()
+            .filter_map(|(credit, artist)| artist.map(|artist| (credit, artist)))
+            .collect())
+    }
+
+    /// Returns the tracks the artist is credited on in albums of other artists, with the role they appear in
+    pub async fn get_appearances(&self, artist_id: i32) -> Result<Vec<(Model, track::Model)>, DbErr> {
+        let credits = Entity::find()
+            .filter(Column::ArtistId.eq(artist_id))
+            .find_also_related(track::Entity)
+            .join(JoinType::InnerJoin, track::Relation::Album.def())
+            .filter(album::Column::ArtistId.ne(artist_id))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(credits.into_iter()
+            .filter_map(|(credit, track)| track.map(|track| (credit, track)))
+            .collect())
+    }
+
+    /// Replaces the credits of the given tracks
+    pub async fn replace_for_tracks(&self, track_ids: Vec<i32>, create_bodies: Vec<TrackArtistCreate>) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        Entity::delete_many()
+            .filter(Column::TrackId.is_in(track_ids))
+            .exec(&txn)
+            .await?;
+        if !create_bodies.is_empty() {
+            let credits = create_bodies.into_iter().map(|create_body| ActiveModel {
+                id: NotSet,
+                track_id: Set(create_body.track_id),
+                artist_id: Set(create_body.artist_id),
+                role: Set(create_body.role)
+            });
+            Entity::insert_many(credits).exec_without_returning(&txn).await?;
+        }
+        txn.commit().await
+    }
+}
@@ -1,14 +1,19 @@
 use std::process::exit;
 use std::sync::Arc;
 use std::time::Duration;
-use axum::routing::get;
+use axum::routing::{get, post};
 use dotenvy::dotenv;
-use scanner::{Scanner};
-use sea_orm::{DatabaseConnection, Database, ConnectOptions};
-use api::AppState;
+use scanner::{Glob, NamingTemplate, Scanner, ScannerOptions};
+use sea_orm::{ActiveEnum, DatabaseConnection, Database, ConnectOptions};
+use api::{AppState, ImageCache};
 use service::album::AlbumService;
+use service::genre::GenreService;
 use service::artist::ArtistService;
 use service::track::TrackService;
+use service::track_artist::TrackArtistService;
+use service::lyrics::LyricsService;
+use service::playlist::PlaylistService;
+use service::scan_job::ScanJobService;
 use tower_http::cors::CorsLayer;
 
 
@@ -37,6 +42,17 @@ async fn init_db() -> DatabaseConnection {
     }
 }
 
+/// Lists every file and directory a scan would skip, without writing to the database
+fn dry_run(scanner: &Scanner) {
+    let stats = scanner.dry_run();
+    let issues = stats.take_issues();
+    println!("-------------------");
+    for issue in &issues {
+        println!("{}\t{}", issue.reason.to_value(), issue.path);
+    }
+    println!("Dry run found {} issues", issues.len());
+}
+
 #[tokio::main]
 async fn main() {
     println!("Bragi is starting up!");
@@ -46,24 +62,126 @@ async fn main() {
     let artist_service = Arc::new(ArtistService::new(db.clone()));
     let album_service = Arc::new(AlbumService::new(db.clone()));
     let track_service = Arc::new(TrackService::new(db.clone()));
+    let track_artist_service = Arc::new(TrackArtistService::new(db.clone()));
+    let genre_service = Arc::new(GenreService::new(db.clone()));
+    let lyrics_service = Arc::new(LyricsService::new(db.clone()));
+    let playlist_service = Arc::new(PlaylistService::new(db.clone()));
+    let scan_job_service = Arc::new(ScanJobService::new(db.clone()));
+    match scan_job_service.fail_stale().await {
+        Ok(0) => {}
+        Ok(stale) => println!("Marked {} scan jobs interrupted by a restart as failed", stale),
+        Err(e) => eprintln!("Unable to recover interrupted scan jobs: {}", e),
+    }
     let library = std::env::var("LIBRARY_PATH").expect("LIBRARY_PATH must be set");
-    let scanner = Scanner::new(artist_service.clone(), album_service.clone(), track_service.clone(), library);
-    scanner.scan_library().await;
-    println!("Done scanning library.");
+    let templates = match std::env::var("NAMING_TEMPLATES") {
+        Ok(templates) => templates.split(';')
+            .map(|template| template.trim())
+            .filter(|template| !template.is_empty())
+            .map(NamingTemplate::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(1)
+            }),
+        Err(_) => NamingTemplate::defaults(),
+    };
+    let exclude_globs = std::env::var("EXCLUDE_GLOBS").unwrap_or_default()
+        .split(';')
+        .map(|glob| glob.trim())
+        .filter(|glob| !glob.is_empty())
+        .map(Glob::new)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1)
+        });
+    let ffmpeg_path = std::env::var("FFMPEG_PATH").unwrap_or_else(|_| ScannerOptions::default().ffmpeg_path);
+    let options = ScannerOptions {
+        hash_track_contents: std::env::var("HASH_TRACK_CONTENTS").is_ok_and(|hash| hash == "true"),
+        workers: std::env::var("SCAN_WORKERS").ok()
+            .and_then(|workers| workers.parse().ok())
+            .unwrap_or(ScannerOptions::default().workers),
+        templates,
+        analyze_loudness: std::env::var("ANALYZE_LOUDNESS").is_ok_and(|analyze| analyze == "true"),
+        ffmpeg_path: ffmpeg_path.clone(),
+        playlist_path: std::env::var("PLAYLIST_PATH").ok(),
+        genre_separators: std::env::var("GENRE_SEPARATORS").ok()
+            .map(|separators| separators.chars().collect())
+            .unwrap_or(ScannerOptions::default().genre_separators),
+        duplicate_tolerance: std::env::var("DUPLICATE_TOLERANCE_MS").ok()
+            .and_then(|tolerance| tolerance.parse().ok())
+            .unwrap_or(ScannerOptions::default().duplicate_tolerance),
+        acoustic_fingerprints: std::env::var("ACOUSTIC_FINGERPRINTS").is_ok_and(|fingerprints| fingerprints == "true"),
+        fpcalc_path: std::env::var("FPCALC_PATH").unwrap_or_else(|_| ScannerOptions::default().fpcalc_path),
+        exclude_globs,
+        follow_symlinks: std::env::var("FOLLOW_SYMLINKS").is_ok_and(|follow| follow == "true"),
+        require_mount_point: std::env::var("REQUIRE_MOUNT_POINT").is_ok_and(|require| require == "true"),
+    };
+    let scanner = Arc::new(Scanner::new(artist_service.clone(), album_service.clone(), track_service.clone(), track_artist_service.clone(), genre_service.clone(), lyrics_service.clone(), playlist_service.clone(), scan_job_service.clone(), library, options));
+    if std::env::args().any(|arg| arg == "--dry-run") {
+        dry_run(&scanner);
+        return
+    }
+    match scanner.clone().scan().await {
+        Ok(scan_job_id) => println!("Done scanning library, scan job {}.", scan_job_id),
+        Err(e) => eprintln!("Unable to scan library: {}", e),
+    }
+
+    if std::env::var("WATCH_LIBRARY").is_ok_and(|watch| watch == "true") {
+        let debounce = std::env::var("WATCH_DEBOUNCE_SECONDS").ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or(5);
+        let watcher = scanner.clone();
+        tokio::spawn(async move {
+            if let Err(e) = watcher.watch(Duration::from_secs(debounce)).await {
+                eprintln!("Error watching library: {}", e);
+            }
+        });
+    }
+
+    let image_cache_dir = std::env::var("IMAGE_CACHE_DIR")
+        .unwrap_or_else(|_| std::env::temp_dir().join("bragi-images").to_string_lossy().to_string());
+    let image_cache_megabytes: u64 = std::env::var("IMAGE_CACHE_MAX_MB").ok()
+        .and_then(|megabytes| megabytes.parse().ok())
+        .unwrap_or(512);
+    let image_cache = Arc::new(ImageCache::new(image_cache_dir.into(), image_cache_megabytes * 1024 * 1024));
 
     let state = AppState {
         artist_service: artist_service.clone(),
         album_service: album_service.clone(),
         track_service: track_service.clone(),
-        scanner: Arc::new(scanner),
+        track_artist_service: track_artist_service.clone(),
+        genre_service: genre_service.clone(),
+        lyrics_service: lyrics_service.clone(),
+        playlist_service: playlist_service.clone(),
+        scan_job_service: scan_job_service.clone(),
+        scanner: scanner.clone(),
+        image_cache: image_cache.clone(),
+        ffmpeg_path,
     };
 
     let app = axum::Router::new()
         .route("/api/artists", get(api::get_all_artists))
         .route("/api/artists/{artist_id}", get(api::get_artist_by_id))
         .route("/api/artists/{artist_id}/albums", get(api::get_albums_by_artist))
+        .route("/api/artists/{artist_id}/appearances", get(api::get_artist_appearances))
+        .route("/api/artists/{artist_id}/image", get(api::get_artist_image))
+        .route("/api/albums/{album_id}/cover", get(api::get_album_cover))
         .route("/api/albums/{album_id}/tracks", get(api::get_tracks_by_album))
-        .route("/api/library/scan", get(api::rescan_library))
+        .route("/api/tracks", get(api::get_tracks))
+        .route("/api/tracks/{track_id}/lyrics", get(api::get_track_lyrics))
+        .route("/api/genres", get(api::get_all_genres))
+        .route("/api/genres/{genre_id}/albums", get(api::get_genre_albums))
+        .route("/api/playlists", get(api::get_all_playlists))
+        .route("/api/playlists/{playlist_id}", get(api::get_playlist_by_id))
+        .route("/api/playlists/{playlist_id}/tracks", get(api::get_playlist_tracks))
+        .route("/api/library/scan", post(api::start_scan))
+        .route("/api/library/scan/dry-run", get(api::dry_run_scan))
+        .route("/api/library/duplicates", get(api::get_duplicates))
+        .route("/api/library/scans", get(api::get_scan_jobs))
+        .route("/api/library/scans/{scan_job_id}", get(api::get_scan_job_by_id))
+        .route("/api/library/scans/{scan_job_id}/issues", get(api::get_scan_issues))
+        .route("/api/library/scans/{scan_job_id}/cancel", post(api::cancel_scan))
         .route("/api/track/{track_id}/play", get(api::stream_track))
         .with_state(state)
         .layer(CorsLayer::permissive());